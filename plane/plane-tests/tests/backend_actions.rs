@@ -1,19 +1,24 @@
 use chrono::Utc;
-use common::test_env::TestEnvironment;
+use common::{test_env::TestEnvironment, timeout::WithTimeout};
 use hyper::StatusCode;
 use plane::{
     client::PlaneClientError,
+    controller::{error::ApiErrorKind, lost_backends::handle_lost_backends},
     database::backend::BackendActionMessage,
-    log_types::LoggableTime,
-    names::{DroneName, Name},
-    protocol::{BackendAction, Heartbeat, MessageFromDrone, MessageToDrone},
+    log_types::{BackendAddr, LoggableTime},
+    names::{DroneName, Name, ProxyName},
+    protocol::{
+        BackendAction, BackendConnectionStats, BackendEventId, BackendStateMessage, Heartbeat,
+        MessageFromDrone, MessageFromProxy, MessageToDrone,
+    },
     types::{
-        ClusterName, ConnectRequest, ConnectResponse, DockerExecutorConfig, DronePoolName,
-        SpawnConfig,
+        backend_state::TerminationReason, BackendState, BackendStatus, BatchConnectRequest,
+        ClusterName, ConnectRequest, ConnectResponse, DockerExecutorConfig, DroneExclusionReason,
+        DronePoolName, KeyConfig, ScheduleCandidate, SpawnConfig,
     },
 };
 use plane_test_macro::plane_test;
-use std::time::Duration;
+use std::{str::FromStr, time::Duration};
 
 mod common;
 
@@ -32,6 +37,10 @@ fn connect_request(cluster: &ClusterName) -> ConnectRequest {
             max_idle_seconds: None,
             use_static_token: false,
             subdomain: None,
+            drone: None,
+            max_wait_seconds: None,
+            fallback_clusters: Vec::new(),
+            reschedule: false,
         }),
         ..Default::default()
     }
@@ -53,6 +62,334 @@ async fn no_drone_available(env: TestEnvironment) {
     ));
 }
 
+/// Tests that a spawn falls back to another cluster when the primary has no drones.
+#[plane_test]
+async fn spawn_falls_back_to_other_cluster(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+
+    let (_, mut drone_connection) = env.mock_drone(&controller, None).await;
+
+    let empty_cluster = ClusterName::from_str("empty.test").unwrap();
+    let mut connect_request = connect_request(&empty_cluster);
+    connect_request
+        .spawn_config
+        .as_mut()
+        .unwrap()
+        .fallback_clusters = vec![env.cluster.clone()];
+
+    let result = client.connect(&connect_request).await.unwrap();
+    assert!(result.spawned);
+    assert_eq!(result.cluster, Some(env.cluster.clone()));
+
+    drone_connection.close().await;
+}
+
+/// Tests that concurrent spawns do not exceed a drone's backend limit.
+#[plane_test]
+async fn drone_max_backends_enforced(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+
+    let (_, mut drone_connection) = env.mock_drone(&controller, Some(5)).await;
+
+    let connects: Vec<_> = (0..10)
+        .map(|_| {
+            let client = client.clone();
+            let connect_request = connect_request(&env.cluster);
+            tokio::spawn(async move { client.connect(&connect_request).await })
+        })
+        .collect();
+
+    let mut spawned = 0;
+    for connect in connects {
+        if connect.await.unwrap().is_ok() {
+            spawned += 1;
+        }
+    }
+    assert_eq!(spawned, 5);
+
+    drone_connection.close().await;
+}
+
+/// Tests that a batch spawn reports each backend individually, including capacity failures.
+#[plane_test]
+async fn batch_spawn_partial_success(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+
+    let (_, mut drone_connection) = env.mock_drone(&controller, Some(3)).await;
+
+    let response = client
+        .connect_batch(&BatchConnectRequest {
+            request: connect_request(&env.cluster),
+            count: 5,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(response.results.len(), 5);
+    let spawned: Vec<_> = response
+        .results
+        .iter()
+        .filter_map(|r| r.as_ref().ok())
+        .collect();
+    assert_eq!(spawned.len(), 3);
+    assert!(spawned.iter().all(|r| r.spawned));
+
+    for result in &response.results {
+        if let Err(error) = result {
+            assert!(matches!(error.kind, ApiErrorKind::NoDroneAvailable));
+        }
+    }
+
+    // Batches of keyed requests are rejected, since the backends would share the key.
+    let mut keyed_request = connect_request(&env.cluster);
+    keyed_request.key = Some(KeyConfig {
+        name: "batch".to_string(),
+        ..Default::default()
+    });
+    let result = client
+        .connect_batch(&BatchConnectRequest {
+            request: keyed_request,
+            count: 2,
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        result,
+        PlaneClientError::PlaneError(_, StatusCode::BAD_REQUEST)
+    ));
+
+    // Batches that wait for drones are rejected, since each spawn would wait in turn.
+    let mut waiting_request = connect_request(&env.cluster);
+    waiting_request
+        .spawn_config
+        .as_mut()
+        .unwrap()
+        .max_wait_seconds = Some(5);
+    let result = client
+        .connect_batch(&BatchConnectRequest {
+            request: waiting_request,
+            count: 2,
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        result,
+        PlaneClientError::PlaneError(_, StatusCode::BAD_REQUEST)
+    ));
+
+    drone_connection.close().await;
+}
+
+/// Tests that a connect with max_wait_seconds waits for a drone to become available.
+#[plane_test]
+async fn connect_waits_for_drone(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+
+    let mut connect_request = connect_request(&env.cluster);
+    connect_request
+        .spawn_config
+        .as_mut()
+        .unwrap()
+        .max_wait_seconds = Some(5);
+
+    let connect = {
+        let client = client.clone();
+        tokio::spawn(async move { client.connect(&connect_request).await })
+    };
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let (_, mut drone_connection) = env.mock_drone(&controller, None).await;
+
+    let result = connect.await.unwrap().unwrap();
+    assert!(result.spawned);
+
+    drone_connection.close().await;
+}
+
+/// Tests that a spawn pinned to a drone is placed on it, and rejected once it drains.
+#[plane_test]
+async fn spawn_pinned_to_drone(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+
+    let mut drones = Vec::new();
+    for _ in 0..2 {
+        let (drone_id, drone_connection) = env.mock_drone(&controller, None).await;
+        drones.push((drone_id, drone_connection));
+    }
+
+    let (pinned_drone, pinned_connection) = &mut drones[1];
+    let mut connect_request = connect_request(&env.cluster);
+    connect_request.spawn_config.as_mut().unwrap().drone = Some(pinned_drone.clone());
+
+    let result = client.connect(&connect_request).await.unwrap();
+    assert!(result.spawned);
+    assert_eq!(result.drone.as_ref(), Some(&*pinned_drone));
+
+    let msg = pinned_connection.recv().await.unwrap();
+    let MessageToDrone::Action(BackendActionMessage {
+        action: BackendAction::Spawn { .. },
+        ..
+    }) = msg
+    else {
+        panic!("Unexpected message: {:?}", msg);
+    };
+
+    client.drain(&env.cluster, pinned_drone).await.unwrap();
+
+    let result = client.connect(&connect_request).await.unwrap_err();
+    let PlaneClientError::PlaneError(api_error, StatusCode::CONFLICT) = result else {
+        panic!("Unexpected error: {:?}", result);
+    };
+    assert!(matches!(
+        api_error.kind,
+        ApiErrorKind::PinnedDroneUnavailable
+    ));
+
+    for (_, mut drone_connection) in drones {
+        drone_connection.close().await;
+    }
+}
+
+/// Tests that a spawn pinned to a drone at its backend limit is rejected as a client error.
+#[plane_test]
+async fn spawn_pinned_to_full_drone(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+    let (drone_id, mut drone_connection) = env.mock_drone(&controller, Some(1)).await;
+
+    let mut connect_request = connect_request(&env.cluster);
+    connect_request.spawn_config.as_mut().unwrap().drone = Some(drone_id.clone());
+
+    let result = client.connect(&connect_request).await.unwrap();
+    assert!(result.spawned);
+
+    let result = client.connect(&connect_request).await.unwrap_err();
+    let PlaneClientError::PlaneError(api_error, StatusCode::CONFLICT) = result else {
+        panic!("Unexpected error: {:?}", result);
+    };
+    assert!(matches!(
+        api_error.kind,
+        ApiErrorKind::PinnedDroneUnavailable
+    ));
+
+    drone_connection.close().await;
+}
+
+/// Tests that a drained drone is skipped by the scheduler, and is eligible again as soon
+/// as it is undrained. The drone never acknowledges either change, since both are
+/// recorded by the controller alone.
+#[plane_test]
+async fn undrain_restores_scheduling(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+    let (drone_id, mut drone_connection) = env.mock_drone(&controller, None).await;
+
+    assert!(client.drain(&env.cluster, &drone_id).await.unwrap().updated);
+
+    let result = client
+        .connect(&connect_request(&env.cluster))
+        .await
+        .unwrap_err();
+    let PlaneClientError::PlaneError(api_error, _) = result else {
+        panic!("Unexpected error: {:?}", result);
+    };
+    assert!(matches!(api_error.kind, ApiErrorKind::NoDroneAvailable));
+
+    assert!(
+        client
+            .undrain(&env.cluster, &drone_id)
+            .await
+            .unwrap()
+            .updated
+    );
+    assert!(
+        !client
+            .undrain(&env.cluster, &drone_id)
+            .await
+            .unwrap()
+            .updated
+    );
+
+    let result = client
+        .connect(&connect_request(&env.cluster))
+        .await
+        .unwrap();
+    assert!(result.spawned);
+    assert_eq!(result.drone, Some(drone_id));
+
+    drone_connection.close().await;
+}
+
+/// Tests that a scheduling dry run explains its choice, creates no backend, and picks
+/// the same drone as the scheduler.
+#[plane_test]
+async fn schedule_dry_run_matches_scheduler(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+
+    let mut drones = Vec::new();
+    for _ in 0..3 {
+        let (drone_id, drone_connection) = env.mock_drone(&controller, None).await;
+        drones.push((drone_id, drone_connection));
+    }
+
+    // The first drone gets a backend, and the last one is draining, which leaves the
+    // second as the only idle, eligible drone.
+    let mut pinned_request = connect_request(&env.cluster);
+    pinned_request.spawn_config.as_mut().unwrap().drone = Some(drones[0].0.clone());
+    client.connect(&pinned_request).await.unwrap();
+    client.drain(&env.cluster, &drones[2].0).await.unwrap();
+
+    let spawn_config = connect_request(&env.cluster).spawn_config.unwrap();
+    let dry_run = client.schedule_dry_run(&spawn_config).await.unwrap();
+
+    assert_eq!(
+        dry_run.placement,
+        Some((env.cluster.clone(), drones[1].0.clone()))
+    );
+    assert_eq!(dry_run.clusters.len(), 1);
+    assert_eq!(
+        dry_run.clusters[0].candidates,
+        vec![
+            ScheduleCandidate {
+                drone: drones[1].0.clone(),
+                backend_count: 0,
+                excluded: None,
+            },
+            ScheduleCandidate {
+                drone: drones[0].0.clone(),
+                backend_count: 1,
+                excluded: None,
+            },
+            ScheduleCandidate {
+                drone: drones[2].0.clone(),
+                backend_count: 0,
+                excluded: Some(DroneExclusionReason::Draining),
+            },
+        ]
+    );
+
+    // The dry run did not create a backend.
+    let dry_run_again = client.schedule_dry_run(&spawn_config).await.unwrap();
+    assert_eq!(dry_run_again, dry_run);
+
+    let result = client
+        .connect(&connect_request(&env.cluster))
+        .await
+        .unwrap();
+    assert_eq!(result.drone, Some(drones[1].0.clone()));
+
+    for (_, mut drone_connection) in drones {
+        drone_connection.close().await;
+    }
+}
+
 /// Tests that a connect succeeds when a drone is available.
 #[plane_test]
 async fn backend_action_resent_if_not_acked(env: TestEnvironment) {
@@ -62,7 +399,7 @@ async fn backend_action_resent_if_not_acked(env: TestEnvironment) {
     let backend_id = {
         let mut drone_connection = controller
             .client()
-            .drone_connection(&env.cluster, &env.pool)
+            .drone_connection(&env.cluster, &env.pool, None)
             .connect(&drone_id)
             .await
             .unwrap();
@@ -109,7 +446,7 @@ async fn backend_action_resent_if_not_acked(env: TestEnvironment) {
         // The message was not acked; a new connection should cause it to be repeated.
         let mut drone_connection = controller
             .client()
-            .drone_connection(&env.cluster, &env.pool)
+            .drone_connection(&env.cluster, &env.pool, None)
             .connect(&drone_id)
             .await
             .unwrap();
@@ -146,7 +483,7 @@ async fn backend_action_resent_if_not_acked(env: TestEnvironment) {
         // The message should not be repeated now.
         let mut drone_connection = controller
             .client()
-            .drone_connection(&env.cluster, &env.pool)
+            .drone_connection(&env.cluster, &env.pool, None)
             .connect(&drone_id)
             .await
             .unwrap();
@@ -170,3 +507,121 @@ async fn backend_action_resent_if_not_acked(env: TestEnvironment) {
         drone_connection.close().await;
     }
 }
+
+/// Tests that a backend whose drone is lost before it becomes ready is replaced on another drone.
+#[plane_test]
+async fn lost_backend_rescheduled(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+    let db = env.db().await;
+
+    let (_, mut lost_connection) = env.mock_drone(&controller, None).await;
+
+    let mut connect_request = connect_request(&env.cluster);
+    connect_request.key = Some(KeyConfig {
+        name: "reschedule".to_string(),
+        ..Default::default()
+    });
+    connect_request.spawn_config.as_mut().unwrap().reschedule = true;
+
+    let result = client.connect(&connect_request).await.unwrap();
+    assert!(result.spawned);
+    let lost_backend = result.backend_id;
+
+    // The drone goes silent before the backend is ready.
+    lost_connection.close().await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let (_, mut drone_connection) = env.mock_drone(&controller, None).await;
+
+    handle_lost_backends(&db, &client, None, chrono::Duration::milliseconds(500))
+        .await
+        .unwrap();
+
+    let status = client.backend_status(&lost_backend).await.unwrap();
+    assert_eq!(status.status, BackendStatus::Terminated);
+    assert_eq!(status.termination_reason, Some(TerminationReason::Lost));
+
+    let msg = drone_connection.recv().await.unwrap();
+    let MessageToDrone::Action(BackendActionMessage {
+        backend_id: replacement,
+        action: BackendAction::Spawn { .. },
+        ..
+    }) = msg
+    else {
+        panic!("Unexpected message: {:?}", msg);
+    };
+    assert_ne!(replacement, lost_backend);
+
+    drone_connection
+        .send(MessageFromDrone::BackendEvent(BackendStateMessage {
+            event_id: BackendEventId::from(1),
+            backend_id: replacement.clone(),
+            state: BackendState::Ready {
+                address: BackendAddr("127.0.0.1:8080".parse().unwrap()),
+            },
+            timestamp: LoggableTime(Utc::now()),
+        }))
+        .unwrap();
+
+    let mut status_stream = client.backend_status_stream(&replacement).await.unwrap();
+    while let Some(status) = status_stream.next().await {
+        if status.status >= BackendStatus::Ready {
+            assert_eq!(status.status, BackendStatus::Ready);
+            break;
+        }
+    }
+
+    // The replacement holds the key of the lost backend.
+    let result = client.connect(&connect_request).await.unwrap();
+    assert!(!result.spawned);
+    assert_eq!(result.backend_id, replacement);
+
+    drone_connection.close().await;
+}
+
+/// Tests that connection counts reported by different proxies are summed.
+#[plane_test]
+async fn active_connections_summed_across_proxies(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+    let db = env.db().await;
+
+    let (_, mut drone_connection) = env.mock_drone(&controller, None).await;
+
+    let result = client
+        .connect(&connect_request(&env.cluster))
+        .await
+        .unwrap();
+    let backend = result.backend_id;
+
+    let mut proxies = Vec::new();
+    for active_connections in [2, 3] {
+        let mut proxy = client
+            .proxy_connection(&env.cluster)
+            .connect(&ProxyName::new_random())
+            .await
+            .unwrap();
+        proxy
+            .send(MessageFromProxy::ConnectionStats(BackendConnectionStats {
+                backend: backend.clone(),
+                active_connections,
+            }))
+            .unwrap();
+        proxies.push(proxy);
+    }
+
+    async {
+        while db.backend().active_connections(&backend).await.unwrap() != Some(5) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+    .with_timeout(10)
+    .await
+    .expect("Connection counts were not summed.");
+
+    for mut proxy in proxies {
+        proxy.close().await;
+    }
+    drone_connection.close().await;
+}
@@ -4,8 +4,8 @@ use plane::{
     names::{Name, ProxyName},
     protocol::{MessageFromProxy, MessageToProxy, RouteInfoRequest, RouteInfoResponse},
     types::{
-        BackendStatus, ConnectRequest, DockerExecutorConfig, DronePoolName, PullPolicy,
-        ResourceLimits, SpawnConfig,
+        backend_state::TerminationReason, BackendStatus, ConnectRequest, DockerExecutorConfig,
+        DronePoolName, PullPolicy, ResourceLimits, SpawnConfig,
     },
 };
 use plane_test_macro::plane_test;
@@ -34,6 +34,7 @@ async fn backend_lifecycle(env: TestEnvironment) {
                 image: "ghcr.io/jamsocket/demo-image-drop-four".to_string(),
                 pull_policy: Some(PullPolicy::IfNotPresent),
                 env: HashMap::default(),
+                args: None,
                 resource_limits: ResourceLimits::default(),
                 credentials: None,
                 mount: None,
@@ -44,6 +45,10 @@ async fn backend_lifecycle(env: TestEnvironment) {
             max_idle_seconds: None,
             use_static_token: false,
             subdomain: None,
+            drone: None,
+            max_wait_seconds: None,
+            fallback_clusters: Vec::new(),
+            reschedule: false,
         }),
         key: None,
         user: None,
@@ -229,3 +234,79 @@ async fn backend_lifecycle(env: TestEnvironment) {
     );
     tracing::info!("Got terminated status.");
 }
+
+#[plane_test]
+async fn backend_exceeding_memory_limit_is_out_of_memory(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+    let _drone = env.drone(&controller).await;
+
+    // Wait for the drone to register.
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+
+    tracing::info!("Requesting backend that exceeds its memory limit.");
+    let connect_request = ConnectRequest {
+        spawn_config: Some(SpawnConfig {
+            id: None,
+            cluster: Some(env.cluster.clone()),
+            pool: DronePoolName::default(),
+            executable: serde_json::to_value(DockerExecutorConfig {
+                image: "alpine:latest".to_string(),
+                pull_policy: Some(PullPolicy::IfNotPresent),
+                env: HashMap::default(),
+                // /dev/zero has no newlines, so tail buffers it until it is killed.
+                args: Some(vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    "tail /dev/zero".to_string(),
+                ]),
+                resource_limits: ResourceLimits {
+                    memory_limit_bytes: Some(16 * 1024 * 1024),
+                    ..ResourceLimits::default()
+                },
+                credentials: None,
+                mount: None,
+                network_name: None,
+            })
+            .unwrap(),
+            lifetime_limit_seconds: None,
+            max_idle_seconds: None,
+            use_static_token: false,
+            subdomain: None,
+            drone: None,
+            max_wait_seconds: None,
+            fallback_clusters: Vec::new(),
+            reschedule: false,
+        }),
+        key: None,
+        user: None,
+        auth: Map::default(),
+    };
+    let response = client.connect(&connect_request).await.unwrap();
+    assert!(response.spawned);
+
+    let mut backend_status_stream = client
+        .backend_status_stream(&response.backend_id)
+        .with_timeout(10)
+        .await
+        .unwrap()
+        .unwrap();
+
+    tracing::info!("Waiting for terminated status.");
+    let terminated = async {
+        loop {
+            let entry = backend_status_stream.next().await.unwrap();
+            if entry.status == BackendStatus::Terminated {
+                return entry;
+            }
+        }
+    }
+    .with_timeout(60)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        terminated.termination_reason,
+        Some(TerminationReason::OutOfMemory)
+    );
+}
@@ -31,6 +31,7 @@ async fn backend_lifecycle_socket(env: TestEnvironment) {
         image: "ghcr.io/jamsocket/demo-image-drop-four".to_string(),
         pull_policy: Some(PullPolicy::IfNotPresent),
         env: HashMap::default(),
+        args: None,
         resource_limits: ResourceLimits::default(),
         credentials: None,
         mount: None,
@@ -48,6 +49,10 @@ async fn backend_lifecycle_socket(env: TestEnvironment) {
             max_idle_seconds: None,
             use_static_token: false,
             subdomain: None,
+            drone: None,
+            max_wait_seconds: None,
+            fallback_clusters: Vec::new(),
+            reschedule: false,
         }),
         key: None,
         user: None,
@@ -277,6 +282,7 @@ async fn backend_lifecycle_socket(env: TestEnvironment) {
         .send_message(MessageToClient::TerminateEvent(TerminateEvent {
             backend_id: backend_id.clone(),
             exit_code: Some(0),
+            oom_killed: false,
         }))
         .await;
 
@@ -1,12 +1,17 @@
 use crate::common::timeout::WithTimeout;
 use common::test_env::TestEnvironment;
-use plane::types::{
-    BackendStatus, ConnectRequest, DockerExecutorConfig, DronePoolName, KeyConfig, PullPolicy,
-    ResourceLimits, SpawnConfig,
+use plane::{
+    client::PlaneClientError,
+    controller::error::{ApiError, ApiErrorKind},
+    names::{BackendName, Name},
+    types::{
+        BackendStatus, ConnectRequest, DockerExecutorConfig, DronePoolName, KeyConfig, PullPolicy,
+        ResourceLimits, SpawnConfig,
+    },
 };
 use plane_test_macro::plane_test;
 use serde_json::Map;
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 mod common;
 
@@ -29,6 +34,7 @@ async fn backend_status_in_response(env: TestEnvironment) {
                 image: "ghcr.io/jamsocket/demo-image-drop-four".to_string(),
                 pull_policy: Some(PullPolicy::IfNotPresent),
                 env: HashMap::default(),
+                args: None,
                 resource_limits: ResourceLimits::default(),
                 credentials: None,
                 mount: None,
@@ -39,6 +45,10 @@ async fn backend_status_in_response(env: TestEnvironment) {
             max_idle_seconds: None,
             use_static_token: false,
             subdomain: None,
+            drone: None,
+            max_wait_seconds: None,
+            fallback_clusters: Vec::new(),
+            reschedule: false,
         }),
         key: Some(KeyConfig {
             name: "reuse-key".to_string(),
@@ -100,3 +110,28 @@ async fn backend_status_in_response(env: TestEnvironment) {
         }
     }
 }
+
+#[plane_test]
+async fn wait_for_status_unknown_backend(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+
+    let result = client
+        .wait_for_status(
+            &BackendName::new_random(),
+            BackendStatus::Ready,
+            Duration::from_secs(10),
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(PlaneClientError::PlaneError(
+            ApiError {
+                kind: ApiErrorKind::NotFound,
+                ..
+            },
+            _
+        ))
+    ));
+}
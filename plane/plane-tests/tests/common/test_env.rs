@@ -1,10 +1,11 @@
 use super::{
     async_drop::AsyncDrop,
     resources::{database::DevDatabase, pebble::Pebble},
+    timeout::WithTimeout,
 };
-use chrono::Duration;
+use chrono::{Duration, Utc};
 use plane::{
-    controller::ControllerServer,
+    controller::{webhook::WebhookConfig, ControllerServer},
     database::PlaneDatabase,
     dns::run_dns_with_listener,
     drone::{
@@ -14,8 +15,11 @@ use plane::{
         },
         Drone, DroneConfig, ExecutorConfig,
     },
+    log_types::LoggableTime,
     names::{AcmeDnsServerName, ControllerName, DroneName, Name},
+    protocol::{Heartbeat, MessageFromDrone},
     proxy::AcmeEabConfiguration,
+    typed_socket::TypedSocket,
     typed_unix_socket::{server::TypedUnixSocketServer, WrappedMessage},
     types::{ClusterName, DronePoolName},
     util::random_string,
@@ -113,6 +117,29 @@ impl TestEnvironment {
             None,
             None,
             None,
+            None,
+        )
+        .await
+        .expect("Unable to construct controller.")
+    }
+
+    pub async fn controller_with_webhook(&mut self, webhook: WebhookConfig) -> ControllerServer {
+        let db = self.db().await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url: Url = format!("http://{}", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+
+        ControllerServer::run_with_listener(
+            db.clone(),
+            listener,
+            ControllerName::new_random(),
+            url,
+            None,
+            None,
+            None,
+            None,
+            Some(webhook),
         )
         .await
         .expect("Unable to construct controller.")
@@ -134,6 +161,7 @@ impl TestEnvironment {
             None,
             None,
             Some(forward_auth.clone()),
+            None,
         )
         .await
         .expect("Unable to construct controller.")
@@ -160,6 +188,7 @@ impl TestEnvironment {
             ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
             db_path: Some(self.scratch_dir.join("drone.db")),
             pool: pool.clone(),
+            max_backends: None,
             auto_prune: None,
             cleanup_min_age: None,
             executor_config: Some(ExecutorConfig::Docker(docker_config)),
@@ -206,6 +235,7 @@ impl TestEnvironment {
             ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
             db_path: Some(self.scratch_dir.join("drone.db")),
             pool: self.pool.clone(),
+            max_backends: None,
             auto_prune: None,
             cleanup_min_age: None,
             executor_config: Some(executor_config),
@@ -218,7 +248,61 @@ impl TestEnvironment {
         DroneWithSocket::new(socket_path, drone).await
     }
 
+    /// Connects to the controller as a drone, without running one, so that the test can
+    /// play the drone's side of the protocol. Returns once the controller considers the
+    /// drone eligible for new backends.
+    pub async fn mock_drone(
+        &mut self,
+        controller: &ControllerServer,
+        max_backends: Option<u32>,
+    ) -> (DroneName, TypedSocket<MessageFromDrone>) {
+        let name = DroneName::new_random();
+        let mut socket = controller
+            .client()
+            .drone_connection(&self.cluster, &self.pool, max_backends)
+            .connect(&name)
+            .await
+            .unwrap();
+        socket
+            .send(MessageFromDrone::Heartbeat(Heartbeat {
+                local_time: LoggableTime(Utc::now()),
+            }))
+            .unwrap();
+
+        let db = self.db().await;
+        let ready = async {
+            loop {
+                let candidates = db
+                    .drone()
+                    .spawn_candidates(&self.cluster, Some(&name))
+                    .await
+                    .unwrap();
+                if candidates
+                    .first()
+                    .is_some_and(|candidate| candidate.exclusion_reason(&self.pool).is_none())
+                {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        };
+        ready
+            .with_timeout(10)
+            .await
+            .expect("Drone did not become ready.");
+
+        (name, socket)
+    }
+
     pub async fn dns(&mut self, controller: &ControllerServer) -> DnsServer {
+        self.dns_with_caa_issuer(controller, None).await
+    }
+
+    pub async fn dns_with_caa_issuer(
+        &mut self,
+        controller: &ControllerServer,
+        caa_issuer: Option<trust_dns_proto::rr::Name>,
+    ) -> DnsServer {
         let client = controller.client();
         let listener = tokio::net::TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))
             .await
@@ -226,7 +310,7 @@ impl TestEnvironment {
         let port = listener.local_addr().unwrap().port();
         let name = AcmeDnsServerName::new_random();
         let handle = tokio::spawn(async move {
-            run_dns_with_listener(name, client, listener, None)
+            run_dns_with_listener(name, client, listener, None, caa_issuer)
                 .await
                 .unwrap();
         });
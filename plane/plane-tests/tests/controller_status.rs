@@ -29,3 +29,19 @@ async fn controller_registers_itself(env: TestEnvironment) {
     let online_controllers = db.controller().online_controllers().await.unwrap();
     assert_eq!(online_controllers.len(), 0);
 }
+
+#[plane_test]
+async fn controller_metrics_returns(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let url = controller.url().join("/ctrl/metrics").unwrap();
+
+    let response = reqwest::get(url).await.unwrap();
+    assert!(response.status().is_success());
+
+    let body = response.text().await.unwrap();
+    assert!(body.contains("# TYPE plane_backends gauge"));
+    assert!(body.contains("# TYPE plane_drones gauge"));
+    assert!(body.contains("# TYPE plane_txt_records gauge"));
+    assert!(body.contains("# TYPE plane_backend_state_messages_total counter"));
+    assert!(body.contains("plane_backend_state_apply_seconds_count 0"));
+}
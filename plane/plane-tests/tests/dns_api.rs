@@ -1,4 +1,4 @@
-use common::test_env::TestEnvironment;
+use common::{test_env::TestEnvironment, timeout::WithTimeout};
 use plane::{
     names::{AcmeDnsServerName, Name, ProxyName},
     protocol::{
@@ -7,6 +7,12 @@ use plane::{
     },
 };
 use plane_test_macro::plane_test;
+use std::str::FromStr;
+use tokio::net::UdpSocket;
+use trust_dns_proto::{
+    op::{Message, MessageType, Query, ResponseCode},
+    rr::{rdata::caa::Value, Name as DnsName, RData, RecordType},
+};
 
 mod common;
 
@@ -66,3 +72,68 @@ async fn dns_api(env: TestEnvironment) {
     assert_eq!(cluster, env.cluster);
     assert_eq!(txt_value.as_deref(), Some("foobaz"));
 }
+
+/// Sends a DNS query over UDP to the given local port and returns the response.
+async fn query(port: u16, name: &str, record_type: RecordType) -> Message {
+    let mut message = Message::new();
+    message
+        .set_id(1)
+        .set_message_type(MessageType::Query)
+        .add_query(Query::query(DnsName::from_str(name).unwrap(), record_type));
+
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket
+        .send_to(&message.to_vec().unwrap(), ("127.0.0.1", port))
+        .await
+        .unwrap();
+
+    let mut buf = [0; 512];
+    let len = socket
+        .recv(&mut buf)
+        .with_timeout(5)
+        .await
+        .unwrap()
+        .unwrap();
+    Message::from_vec(&buf[..len]).unwrap()
+}
+
+#[plane_test]
+async fn caa_query_for_cluster(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let dns = env
+        .dns_with_caa_issuer(
+            &controller,
+            Some(DnsName::from_str("letsencrypt.org").unwrap()),
+        )
+        .await;
+    let cluster = env.cluster.to_string();
+
+    for name in [cluster.clone(), format!("*.{}", cluster)] {
+        let response = query(dns.port, &name, RecordType::CAA).await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert!(response.authoritative());
+
+        let [answer] = response.answers() else {
+            panic!("Expected one answer, got {:?}", response.answers());
+        };
+        let Some(RData::CAA(caa)) = answer.data() else {
+            panic!("Expected CAA record, got {:?}", answer.data());
+        };
+        assert!(caa.tag().is_issue());
+        let Value::Issuer(Some(issuer), _) = caa.value() else {
+            panic!("Expected issuer, got {:?}", caa.value());
+        };
+        assert_eq!(issuer, &DnsName::from_str("letsencrypt.org").unwrap());
+    }
+}
+
+#[plane_test]
+async fn caa_query_disabled(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let dns = env.dns(&controller).await;
+
+    let response = query(dns.port, &env.cluster.to_string(), RecordType::CAA).await;
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(response.authoritative());
+    assert!(response.answers().is_empty());
+}
@@ -31,6 +31,7 @@ async fn drone_pools(env: TestEnvironment) {
                 image: "ghcr.io/jamsocket/demo-image-drop-four".to_string(),
                 pull_policy: Some(PullPolicy::IfNotPresent),
                 env: HashMap::default(),
+                args: None,
                 resource_limits: ResourceLimits::default(),
                 credentials: None,
                 mount: None,
@@ -41,6 +42,10 @@ async fn drone_pools(env: TestEnvironment) {
             max_idle_seconds: None,
             use_static_token: false,
             subdomain: None,
+            drone: None,
+            max_wait_seconds: None,
+            fallback_clusters: Vec::new(),
+            reschedule: false,
         }),
         key: Some(KeyConfig {
             name: "reuse-key".to_string(),
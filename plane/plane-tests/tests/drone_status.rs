@@ -1,6 +1,10 @@
 use common::test_env::TestEnvironment;
-use plane::database::{node::NodeConnectionStatusChangeNotification, subscribe::Subscription};
+use plane::database::{
+    drone::DroneDrainedNotification, node::NodeConnectionStatusChangeNotification,
+    subscribe::Subscription,
+};
 use plane_test_macro::plane_test;
+use std::time::Duration;
 
 mod common;
 
@@ -18,3 +22,44 @@ async fn controller_registers_drone(env: TestEnvironment) {
     assert_eq!(drone_status_list.len(), 1);
     assert_eq!(drone_status_list[0].id, drone_status.payload.node_id);
 }
+
+#[plane_test]
+async fn drone_advertises_max_backends(env: TestEnvironment) {
+    let controller = env.controller().await;
+    let client = controller.client();
+
+    let (_, mut drone_connection) = env.mock_drone(&controller, Some(5)).await;
+
+    let cluster_state = client.cluster_state(&env.cluster).await.unwrap();
+    assert_eq!(cluster_state.drones.len(), 1);
+    assert_eq!(cluster_state.drones[0].max_backends, Some(5));
+
+    drone_connection.close().await;
+}
+
+#[plane_test]
+async fn draining_idle_drone_emits_drained(env: TestEnvironment) {
+    let db = env.db().await;
+    let controller = env.controller().await;
+    let client = controller.client();
+    let (drone, mut drone_connection) = env.mock_drone(&controller, None).await;
+
+    let mut listener: Subscription<DroneDrainedNotification> = db.subscribe();
+    let drone_id = db
+        .node()
+        .get_id(&env.cluster, &drone)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let result = client.drain(&env.cluster, &drone).await.unwrap();
+    assert!(result.updated);
+
+    let notification = tokio::time::timeout(Duration::from_secs(5), listener.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(notification.payload.drone_id, drone_id);
+
+    drone_connection.close().await;
+}
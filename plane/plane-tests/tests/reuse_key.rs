@@ -29,6 +29,7 @@ async fn reuse_key(env: TestEnvironment) {
                 image: "ghcr.io/jamsocket/demo-image-drop-four".to_string(),
                 pull_policy: Some(PullPolicy::IfNotPresent),
                 env: HashMap::default(),
+                args: None,
                 resource_limits: ResourceLimits::default(),
                 credentials: None,
                 mount: None,
@@ -39,6 +40,10 @@ async fn reuse_key(env: TestEnvironment) {
             max_idle_seconds: None,
             use_static_token: false,
             subdomain: None,
+            drone: None,
+            max_wait_seconds: None,
+            fallback_clusters: Vec::new(),
+            reschedule: false,
         }),
         key: Some(KeyConfig {
             name: "reuse-key".to_string(),
@@ -28,6 +28,7 @@ async fn subdomains(env: TestEnvironment) {
                 image: "ghcr.io/jamsocket/demo-image-drop-four".to_string(),
                 pull_policy: Some(PullPolicy::IfNotPresent),
                 env: HashMap::default(),
+                args: None,
                 resource_limits: ResourceLimits::default(),
                 credentials: None,
                 mount: None,
@@ -38,6 +39,10 @@ async fn subdomains(env: TestEnvironment) {
             max_idle_seconds: None,
             use_static_token: false,
             subdomain: None,
+            drone: None,
+            max_wait_seconds: None,
+            fallback_clusters: Vec::new(),
+            reschedule: false,
         }),
         key: None,
         user: None,
@@ -0,0 +1,181 @@
+use axum::{body::Bytes, http::HeaderMap};
+use chrono::Utc;
+use common::{test_env::TestEnvironment, timeout::WithTimeout};
+use plane::{
+    controller::{
+        lost_backends::handle_lost_backends,
+        webhook::{
+            sign_payload, BackendStateWebhookPayload, WebhookConfig, WebhookNotifier,
+            WEBHOOK_SIGNATURE_HEADER,
+        },
+    },
+    database::backend::BackendActionMessage,
+    log_types::{BackendAddr, LoggableTime},
+    protocol::{
+        BackendAction, BackendEventId, BackendStateMessage, MessageFromDrone, MessageToDrone,
+    },
+    types::{
+        backend_state::TerminationReason, BackendState, ClusterName, ConnectRequest,
+        DockerExecutorConfig, DronePoolName, SpawnConfig,
+    },
+};
+use plane_test_macro::plane_test;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::mpsc;
+use url::Url;
+
+mod common;
+
+const SECRET: &str = "webhook-secret";
+
+/// Starts an HTTP server that records the headers and body of each request it receives.
+async fn webhook_sink() -> (Url, mpsc::UnboundedReceiver<(HeaderMap, Bytes)>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let app = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move |headers: HeaderMap, body: Bytes| async move {
+            tx.send((headers, body)).unwrap();
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!(
+        "http://127.0.0.1:{}/hook",
+        listener.local_addr().unwrap().port()
+    ))
+    .unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (url, rx)
+}
+
+/// Returns a connect request for a backend in the given cluster and pool.
+fn connect_request(cluster: &ClusterName, pool: &DronePoolName) -> ConnectRequest {
+    let executable =
+        serde_json::to_value(DockerExecutorConfig::from_image_with_defaults("alpine")).unwrap();
+    ConnectRequest {
+        spawn_config: Some(SpawnConfig {
+            id: None,
+            cluster: Some(cluster.clone()),
+            pool: pool.clone(),
+            executable,
+            lifetime_limit_seconds: None,
+            max_idle_seconds: None,
+            use_static_token: false,
+            subdomain: None,
+            drone: None,
+            max_wait_seconds: None,
+            fallback_clusters: Vec::new(),
+            reschedule: false,
+        }),
+        ..Default::default()
+    }
+}
+
+#[plane_test]
+async fn webhook_reports_ready_transition(env: TestEnvironment) {
+    let (url, mut requests) = webhook_sink().await;
+    let controller = env
+        .controller_with_webhook(WebhookConfig {
+            url,
+            secret: Some(SECRET.to_string()),
+        })
+        .await;
+    let client = controller.client();
+
+    let (_, mut drone_connection) = env.mock_drone(&controller, None).await;
+
+    client
+        .connect(&connect_request(&env.cluster, &env.pool))
+        .await
+        .unwrap();
+
+    let msg = drone_connection.recv().await.unwrap();
+    let MessageToDrone::Action(BackendActionMessage {
+        backend_id,
+        action: BackendAction::Spawn { .. },
+        ..
+    }) = msg
+    else {
+        panic!("Unexpected message: {:?}", msg);
+    };
+
+    let ready_state = BackendState::Ready {
+        address: BackendAddr("127.0.0.1:8080".parse().unwrap()),
+    };
+    drone_connection
+        .send(MessageFromDrone::BackendEvent(BackendStateMessage {
+            event_id: BackendEventId::from(1),
+            backend_id: backend_id.clone(),
+            state: ready_state.clone(),
+            timestamp: LoggableTime(Utc::now()),
+        }))
+        .unwrap();
+
+    let (headers, body) = requests.recv().with_timeout(10).await.unwrap().unwrap();
+
+    assert_eq!(
+        headers.get(WEBHOOK_SIGNATURE_HEADER).unwrap(),
+        sign_payload(SECRET, &body).as_str()
+    );
+
+    let payload: BackendStateWebhookPayload = serde_json::from_slice(&body).unwrap();
+    assert_eq!(payload.cluster, env.cluster);
+    assert_eq!(payload.backend_id, backend_id);
+    assert_eq!(payload.old_state, BackendState::Scheduled);
+    assert_eq!(payload.new_state, ready_state);
+
+    // The payload uses snake_case field names with tagged states.
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["new_state"]["status"], "ready");
+    assert!(json["timestamp"].is_i64());
+
+    drone_connection.close().await;
+}
+
+/// Tests that state changes made by the controller itself, rather than reported by a
+/// drone, are posted to the webhook.
+#[plane_test]
+async fn webhook_reports_lost_backend(env: TestEnvironment) {
+    let (url, mut requests) = webhook_sink().await;
+    let controller = env.controller().await;
+    let client = controller.client();
+    let db = env
+        .db()
+        .await
+        .with_webhook(Arc::new(WebhookNotifier::new(WebhookConfig {
+            url,
+            secret: None,
+        })));
+
+    let (_, mut drone_connection) = env.mock_drone(&controller, None).await;
+
+    let backend_id = client
+        .connect(&connect_request(&env.cluster, &env.pool))
+        .await
+        .unwrap()
+        .backend_id;
+
+    // The drone goes silent before the backend is ready.
+    drone_connection.close().await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    handle_lost_backends(&db, &client, None, chrono::Duration::milliseconds(500))
+        .await
+        .unwrap();
+
+    let (_, body) = requests.recv().with_timeout(10).await.unwrap().unwrap();
+
+    let payload: BackendStateWebhookPayload = serde_json::from_slice(&body).unwrap();
+    assert_eq!(payload.backend_id, backend_id);
+    assert_eq!(payload.old_state, BackendState::Scheduled);
+    assert!(matches!(
+        payload.new_state,
+        BackendState::Terminated {
+            reason: Some(TerminationReason::Lost),
+            ..
+        }
+    ));
+}
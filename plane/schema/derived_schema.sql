@@ -108,7 +108,10 @@ CREATE TABLE public.backend (
     state jsonb NOT NULL,
     static_token character varying(256),
     subdomain character varying(255),
-    last_status_number integer
+    last_status_number integer,
+    reschedule_request jsonb,
+    retry_of character varying(255),
+    retry_count integer DEFAULT 0 NOT NULL
 );
 
 
@@ -198,6 +201,27 @@ COMMENT ON COLUMN public.backend.subdomain IS 'Optional subdomain for session ba
 COMMENT ON COLUMN public.backend.last_status_number IS 'Number representation of last_status, used for ordering.';
 
 
+--
+-- Name: COLUMN backend.reschedule_request; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON COLUMN public.backend.reschedule_request IS 'The connect request to re-run if the backend''s drone is lost before the backend is ready, or null if the backend should not be rescheduled.';
+
+
+--
+-- Name: COLUMN backend.retry_of; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON COLUMN public.backend.retry_of IS 'If this backend was rescheduled from a lost backend, the ID of that backend.';
+
+
+--
+-- Name: COLUMN backend.retry_count; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON COLUMN public.backend.retry_count IS 'The number of times the original request for this backend has been rescheduled.';
+
+
 --
 -- Name: backend_action; Type: TABLE; Schema: public; Owner: postgres
 --
@@ -329,6 +353,55 @@ COMMENT ON COLUMN public.backend_key.fencing_token IS 'A number that monotonical
 COMMENT ON COLUMN public.backend_key.allow_renew IS 'If false, the key cannot be renewed for this backend, forcing the backend to be terminated.';
 
 
+--
+-- Name: backend_proxy_connections; Type: TABLE; Schema: public; Owner: postgres
+--
+
+CREATE TABLE public.backend_proxy_connections (
+    backend_id character varying(255) NOT NULL,
+    proxy_id integer NOT NULL,
+    active_connections integer NOT NULL,
+    last_reported timestamp with time zone NOT NULL
+);
+
+
+ALTER TABLE public.backend_proxy_connections OWNER TO postgres;
+
+--
+-- Name: TABLE backend_proxy_connections; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON TABLE public.backend_proxy_connections IS 'The number of open connections to each backend through each proxy, as last reported by the proxy.';
+
+
+--
+-- Name: COLUMN backend_proxy_connections.backend_id; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON COLUMN public.backend_proxy_connections.backend_id IS 'The backend the connections are to.';
+
+
+--
+-- Name: COLUMN backend_proxy_connections.proxy_id; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON COLUMN public.backend_proxy_connections.proxy_id IS 'The node ID of the proxy the connections go through.';
+
+
+--
+-- Name: COLUMN backend_proxy_connections.active_connections; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON COLUMN public.backend_proxy_connections.active_connections IS 'The number of open connections as of the last report.';
+
+
+--
+-- Name: COLUMN backend_proxy_connections.last_reported; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON COLUMN public.backend_proxy_connections.last_reported IS 'When the proxy last reported its connection count.';
+
+
 --
 -- Name: backend_state; Type: TABLE; Schema: public; Owner: postgres
 --
@@ -469,7 +542,8 @@ CREATE TABLE public.drone (
     draining boolean DEFAULT false NOT NULL,
     last_heartbeat timestamp with time zone,
     last_local_time timestamp with time zone,
-    pool character varying(255) DEFAULT ''::character varying NOT NULL
+    pool character varying(255) DEFAULT ''::character varying NOT NULL,
+    max_backends integer
 );
 
 
@@ -524,6 +598,13 @@ COMMENT ON COLUMN public.drone.last_local_time IS 'The last reported local times
 COMMENT ON COLUMN public.drone.pool IS 'The pool to which the drone is assigned (default pool is an empty string).';
 
 
+--
+-- Name: COLUMN drone.max_backends; Type: COMMENT; Schema: public; Owner: postgres
+--
+
+COMMENT ON COLUMN public.drone.max_backends IS 'The maximum number of non-terminated backends the drone advertises it can run, or null if unlimited.';
+
+
 --
 -- Name: drone_id_seq; Type: SEQUENCE; Schema: public; Owner: postgres
 --
@@ -855,6 +936,14 @@ ALTER TABLE ONLY public.backend
     ADD CONSTRAINT backend_pkey PRIMARY KEY (id);
 
 
+--
+-- Name: backend_proxy_connections backend_proxy_connections_pkey; Type: CONSTRAINT; Schema: public; Owner: postgres
+--
+
+ALTER TABLE ONLY public.backend_proxy_connections
+    ADD CONSTRAINT backend_proxy_connections_pkey PRIMARY KEY (backend_id, proxy_id);
+
+
 --
 -- Name: backend_state backend_state_pkey; Type: CONSTRAINT; Schema: public; Owner: postgres
 --
@@ -931,6 +1020,13 @@ CREATE INDEX idx_backend_action_pending ON public.backend_action USING btree (dr
 CREATE INDEX idx_backend_drone_id ON public.backend USING btree (drone_id) WHERE ((last_status)::text <> 'terminated'::text);
 
 
+--
+-- Name: idx_backend_drone_id_status_number; Type: INDEX; Schema: public; Owner: postgres
+--
+
+CREATE INDEX idx_backend_drone_id_status_number ON public.backend USING btree (drone_id, last_status_number);
+
+
 --
 -- Name: idx_backend_state_created_at; Type: INDEX; Schema: public; Owner: postgres
 --
@@ -1013,6 +1109,22 @@ ALTER TABLE ONLY public.backend_key
     ADD CONSTRAINT backend_key_id_fkey FOREIGN KEY (id) REFERENCES public.backend(id);
 
 
+--
+-- Name: backend_proxy_connections backend_proxy_connections_backend_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: postgres
+--
+
+ALTER TABLE ONLY public.backend_proxy_connections
+    ADD CONSTRAINT backend_proxy_connections_backend_id_fkey FOREIGN KEY (backend_id) REFERENCES public.backend(id);
+
+
+--
+-- Name: backend_proxy_connections backend_proxy_connections_proxy_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: postgres
+--
+
+ALTER TABLE ONLY public.backend_proxy_connections
+    ADD CONSTRAINT backend_proxy_connections_proxy_id_fkey FOREIGN KEY (proxy_id) REFERENCES public.node(id);
+
+
 --
 -- Name: backend_state backend_state_backend_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: postgres
 --
@@ -0,0 +1,3 @@
+alter table drone add column max_backends integer;
+
+comment on column drone.max_backends is 'The maximum number of non-terminated backends the drone advertises it can run, or null if unlimited.';
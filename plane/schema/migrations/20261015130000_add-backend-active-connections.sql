@@ -0,0 +1,13 @@
+create table backend_proxy_connections (
+    backend_id varchar(255) not null references backend(id),
+    proxy_id integer not null references node(id),
+    active_connections integer not null,
+    last_reported timestamptz not null,
+    primary key (backend_id, proxy_id)
+);
+
+comment on table backend_proxy_connections is 'The number of open connections to each backend through each proxy, as last reported by the proxy.';
+comment on column backend_proxy_connections.backend_id is 'The backend the connections are to.';
+comment on column backend_proxy_connections.proxy_id is 'The node ID of the proxy the connections go through.';
+comment on column backend_proxy_connections.active_connections is 'The number of open connections as of the last report.';
+comment on column backend_proxy_connections.last_reported is 'When the proxy last reported its connection count.';
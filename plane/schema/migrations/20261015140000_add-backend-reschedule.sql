@@ -0,0 +1,10 @@
+alter table backend add column reschedule_request jsonb;
+alter table backend add column retry_of varchar(255);
+alter table backend add column retry_count integer not null default 0;
+
+-- Supports finding backends that are not yet ready on drones that have stopped sending heartbeats.
+create index idx_backend_drone_id_status_number on backend(drone_id, last_status_number);
+
+comment on column backend.reschedule_request is 'The connect request to re-run if the backend''s drone is lost before the backend is ready, or null if the backend should not be rescheduled.';
+comment on column backend.retry_of is 'If this backend was rescheduled from a lost backend, the ID of that backend.';
+comment on column backend.retry_count is 'The number of times the original request for this backend has been rescheduled.';
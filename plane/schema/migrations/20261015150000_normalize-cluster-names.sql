@@ -0,0 +1,18 @@
+-- Cluster names are normalized when parsed (the hostname is lowercased and a trailing dot is
+-- removed), so rows stored under another spelling would no longer match. Rewrite them in the
+-- normalized form. Internationalized hostnames stored in Unicode form are not converted to
+-- punycode here and must be renamed by hand.
+update node
+set cluster = regexp_replace(lower(cluster), '\.(:|$)', '\1')
+where cluster is not null
+and cluster != regexp_replace(lower(cluster), '\.(:|$)', '\1');
+
+update backend
+set cluster = regexp_replace(lower(cluster), '\.(:|$)', '\1')
+where cluster != regexp_replace(lower(cluster), '\.(:|$)', '\1');
+
+-- TXT records only live for the duration of an ACME order, and the table is keyed by cluster,
+-- so rather than risk a conflict, drop records stored under a non-normalized name. The proxy
+-- that held the lease will request a new one.
+delete from acme_txt_entries
+where cluster != regexp_replace(lower(cluster), '\.(:|$)', '\1');
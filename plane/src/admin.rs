@@ -78,6 +78,13 @@ fn show_error(error: &PlaneClientError) {
         PlaneClientError::SendFailed => {
             eprintln!("{}", "Failed to send message to channel".bright_red());
         }
+        PlaneClientError::StatusTimeout(status) => {
+            eprintln!(
+                "{}: {}",
+                "Timed out waiting for backend status".bright_red(),
+                status.to_string().magenta()
+            );
+        }
     }
 }
 
@@ -131,6 +138,14 @@ pub enum AdminCommand {
         /// Optionally specify a subdomain for this backend.
         #[clap(long)]
         subdomain: Option<Subdomain>,
+
+        /// Optionally place the backend on this drone instead of letting Plane choose one.
+        #[clap(long)]
+        drone: Option<DroneName>,
+
+        /// Spawn a replacement backend if the drone is lost before the backend is ready.
+        #[clap(long)]
+        reschedule: bool,
     },
     Terminate {
         backend: BackendName,
@@ -148,6 +163,13 @@ pub enum AdminCommand {
         #[clap(long)]
         drone: DroneName,
     },
+    Undrain {
+        #[clap(long)]
+        cluster: ClusterName,
+
+        #[clap(long)]
+        drone: DroneName,
+    },
     PutDummyDns {
         #[clap(long)]
         cluster: ClusterName,
@@ -180,6 +202,8 @@ pub async fn run_admin_command_inner(opts: AdminOpts) -> Result<(), PlaneClientE
             pool,
             mount,
             subdomain,
+            drone,
+            reschedule,
         } => {
             let mut executor_config = DockerExecutorConfig::from_image_with_defaults(image);
             executor_config.mount = mount.map(Mount::Path);
@@ -194,6 +218,10 @@ pub async fn run_admin_command_inner(opts: AdminOpts) -> Result<(), PlaneClientE
                 max_idle_seconds: Some(max_idle_seconds),
                 use_static_token: static_token,
                 subdomain,
+                drone,
+                max_wait_seconds: None,
+                fallback_clusters: Vec::new(),
+                reschedule,
             };
             let key_config = key.map(|name| KeyConfig {
                 name,
@@ -288,6 +316,17 @@ pub async fn run_admin_command_inner(opts: AdminOpts) -> Result<(), PlaneClientE
                 );
             }
         }
+        AdminCommand::Undrain { cluster, drone } => {
+            let result = client.undrain(&cluster, &drone).await?;
+            if result.updated {
+                println!("Drone {} undrained.", drone.to_string().bright_green());
+            } else {
+                println!(
+                    "Drone {} was not draining.",
+                    drone.to_string().bright_green()
+                );
+            }
+        }
         AdminCommand::Status => {
             let status = client.status().await?;
             println!("Status: {}", status.status.to_string().bright_cyan());
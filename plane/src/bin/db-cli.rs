@@ -28,7 +28,19 @@ enum Command {
         #[clap(long)]
         cluster: Option<ClusterName>,
     },
-    ListBackends,
+    ListClusters,
+    ListBackends {
+        #[clap(long)]
+        cluster: Option<ClusterName>,
+
+        /// Only list backends on this drone (requires --cluster).
+        #[clap(long, requires = "cluster")]
+        drone: Option<DroneName>,
+
+        /// Only list backends whose latest status is this status.
+        #[clap(long, value_parser = parse_backend_status)]
+        status: Option<BackendStatus>,
+    },
     TerminationCandidates {
         #[clap(long)]
         cluster: ClusterName,
@@ -54,6 +66,10 @@ enum Command {
     },
 }
 
+fn parse_backend_status(s: &str) -> Result<BackendStatus, String> {
+    BackendStatus::try_from(s.to_string()).map_err(|_| format!("Invalid backend status: {}", s))
+}
+
 async fn main_inner(opts: Opts) -> anyhow::Result<()> {
     let db = connect(&opts.db).await?;
 
@@ -124,10 +140,63 @@ async fn main_inner(opts: Opts) -> anyhow::Result<()> {
                 }
             }
         }
-        Command::ListBackends => {
+        Command::ListClusters => {
+            let metrics = db.cluster().metrics().await?;
+
+            for (cluster, summary) in metrics.summaries() {
+                let backends = summary
+                    .backends_by_status
+                    .iter()
+                    .map(|(status, count)| format!("{}={}", status, count))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                println!(
+                    "{} drones={} txt_records={} {}",
+                    cluster.purple(),
+                    summary.drones.to_string().green(),
+                    summary.txt_record_count.to_string().yellow(),
+                    backends.blue(),
+                );
+            }
+        }
+        Command::ListBackends {
+            cluster,
+            drone,
+            status,
+        } => {
+            let drone_id = match (&cluster, &drone) {
+                (Some(cluster), Some(drone)) => {
+                    let Some(drone_id) = db.node().get_id(cluster, drone).await? else {
+                        println!("No such drone: {} on {}", drone, cluster);
+                        return Ok(());
+                    };
+                    Some(drone_id)
+                }
+                _ => None,
+            };
+
             let backends = db.backend().list_backends().await?;
 
             for backend in backends {
+                if let Some(cluster) = &cluster {
+                    if backend.cluster != cluster.as_str() {
+                        continue;
+                    }
+                }
+
+                if let Some(drone_id) = drone_id {
+                    if backend.drone_id != drone_id {
+                        continue;
+                    }
+                }
+
+                if let Some(status) = status {
+                    if backend.state.status() != status {
+                        continue;
+                    }
+                }
+
                 println!(
                     "{} {} {} {} {}",
                     backend.id.to_string().blue(),
@@ -156,6 +225,7 @@ async fn main_inner(opts: Opts) -> anyhow::Result<()> {
                     termination: None,
                     reason: Some(TerminationReason::Lost),
                     exit_code: None,
+                    message: None,
                 };
 
                 println!("");
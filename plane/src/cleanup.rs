@@ -23,6 +23,11 @@ pub async fn run_cleanup(
 
     db.clean_up_tokens().await?;
 
+    let expired_txt_records = db.acme().clean_up_expired_txt_records().await?;
+    if expired_txt_records > 0 {
+        tracing::info!(expired_txt_records, "Removed expired ACME TXT records");
+    }
+
     tracing::info!("Done running cleanup");
 
     Ok(())
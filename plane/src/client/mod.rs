@@ -5,12 +5,14 @@ use crate::{
     protocol::{MessageFromDns, MessageFromDrone, MessageFromProxy},
     typed_socket::client::TypedSocketConnector,
     types::{
-        backend_state::BackendStatusStreamEntry, ClusterName, ClusterState, ConnectRequest,
-        ConnectResponse, DrainResult, DronePoolName, RevokeRequest,
+        backend_state::BackendStatusStreamEntry, BackendStatus, BatchConnectRequest,
+        BatchConnectResponse, ClusterName, ClusterState, ConnectRequest, ConnectResponse,
+        DrainResult, DronePoolName, RevokeRequest, ScheduleDryRunResponse, SpawnConfig,
     },
 };
 use reqwest::{Response, StatusCode};
 use serde::de::DeserializeOwned;
+use std::time::Duration;
 use url::{form_urlencoded, Url};
 pub mod controller_address;
 mod sse;
@@ -43,6 +45,9 @@ pub enum PlaneClientError {
 
     #[error("Send error")]
     SendFailed,
+
+    #[error("Timed out waiting for backend to reach status {0}.")]
+    StatusTimeout(BackendStatus),
 }
 
 #[derive(Clone)]
@@ -71,15 +76,24 @@ impl PlaneClient {
         &self,
         cluster: &ClusterName,
         pool: &DronePoolName,
+        max_backends: Option<u32>,
     ) -> TypedSocketConnector<MessageFromDrone> {
         let base_path = format!("/ctrl/c/{}/drone-socket", cluster);
-        let addr = if pool.is_default() {
+
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        if !pool.is_default() {
+            query.append_pair("pool", pool.as_str());
+        }
+        if let Some(max_backends) = max_backends {
+            query.append_pair("max_backends", &max_backends.to_string());
+        }
+        let query = query.finish();
+
+        let addr = if query.is_empty() {
             self.controller_address.join(&base_path)
         } else {
-            let encoded_pool: String =
-                form_urlencoded::byte_serialize(pool.as_str().as_bytes()).collect();
             self.controller_address
-                .join(&format!("{}?pool={}", base_path, encoded_pool))
+                .join(&format!("{}?{}", base_path, query))
         }
         .to_websocket_address();
         TypedSocketConnector::new(addr)
@@ -114,6 +128,26 @@ impl PlaneClient {
         Ok(response)
     }
 
+    pub async fn connect_batch(
+        &self,
+        batch_request: &BatchConnectRequest,
+    ) -> Result<BatchConnectResponse, PlaneClientError> {
+        let addr = self.controller_address.join("/ctrl/connect-batch");
+
+        let response = authed_post(&self.client, &addr, batch_request).await?;
+        Ok(response)
+    }
+
+    pub async fn schedule_dry_run(
+        &self,
+        spawn_config: &SpawnConfig,
+    ) -> Result<ScheduleDryRunResponse, PlaneClientError> {
+        let addr = self.controller_address.join("/ctrl/schedule-dry-run");
+
+        let response = authed_post(&self.client, &addr, spawn_config).await?;
+        Ok(response)
+    }
+
     pub async fn drain(
         &self,
         cluster: &ClusterName,
@@ -126,6 +160,20 @@ impl PlaneClient {
         let result: DrainResult = authed_post(&self.client, &addr, &()).await?;
         Ok(result)
     }
+
+    pub async fn undrain(
+        &self,
+        cluster: &ClusterName,
+        drone: &DroneName,
+    ) -> Result<DrainResult, PlaneClientError> {
+        let addr = self
+            .controller_address
+            .join(&format!("/ctrl/c/{}/d/{}/undrain", cluster, drone));
+
+        let result: DrainResult = authed_post(&self.client, &addr, &()).await?;
+        Ok(result)
+    }
+
     pub async fn soft_terminate(&self, backend_id: &BackendName) -> Result<(), PlaneClientError> {
         let addr = self
             .controller_address
@@ -184,6 +232,44 @@ impl PlaneClient {
         Ok(stream)
     }
 
+    /// Waits until the backend has reached the given status or any status after it,
+    /// and returns the first status entry that satisfies this.
+    ///
+    /// Since terminal statuses come after every other status, callers waiting for
+    /// `Ready` should check whether the returned status is actually `Ready`.
+    ///
+    /// Returns `PlaneError` with `NotFound` if the backend does not exist, and
+    /// `StatusTimeout` if the status is not reached within `timeout`.
+    pub async fn wait_for_status(
+        &self,
+        backend_id: &BackendName,
+        status: BackendStatus,
+        timeout: Duration,
+    ) -> Result<BackendStatusStreamEntry, PlaneClientError> {
+        let wait = async {
+            let current = self.backend_status(backend_id).await?;
+            if current.status >= status {
+                return Ok(current);
+            }
+
+            let mut stream = self.backend_status_stream(backend_id).await?;
+            loop {
+                // SseStream::next reconnects on failure, so it never returns None in practice.
+                let Some(entry) = stream.next().await else {
+                    continue;
+                };
+
+                if entry.status >= status {
+                    return Ok(entry);
+                }
+            }
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| PlaneClientError::StatusTimeout(status))?
+    }
+
     pub async fn cluster_state(
         &self,
         cluster: &ClusterName,
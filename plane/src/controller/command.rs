@@ -1,4 +1,4 @@
-use super::ControllerConfig;
+use super::{webhook::WebhookConfig, ControllerConfig};
 use crate::{
     names::{ControllerName, Name},
     types::ClusterName,
@@ -34,6 +34,16 @@ pub struct ControllerOpts {
     /// (after stripping `/ctrl` and everything before it).
     #[clap(long)]
     forward_auth: Option<Url>,
+
+    /// URL to POST a JSON notification to whenever this controller applies a backend
+    /// state change reported by a drone.
+    #[clap(long)]
+    webhook_url: Option<Url>,
+
+    /// Secret used to sign webhook requests. The HMAC-SHA256 of the request body is
+    /// sent in the `x-plane-signature` header.
+    #[clap(long, requires = "webhook_url")]
+    webhook_secret: Option<String>,
 }
 
 impl ControllerOpts {
@@ -56,6 +66,10 @@ impl ControllerOpts {
             cleanup_min_age_days: self.cleanup_min_age_days,
             cleanup_batch_size: None,
             forward_auth: self.forward_auth,
+            webhook: self.webhook_url.map(|url| WebhookConfig {
+                url,
+                secret: self.webhook_secret,
+            }),
         })
     }
 }
@@ -1,70 +1,85 @@
-use super::error::{err_to_response, ApiErrorKind};
+use super::error::{err_to_response, to_api_error, ApiError, ApiErrorKind};
 use super::Controller;
 use crate::controller::error::IntoApiError;
-use crate::database::connect::ConnectError;
-use crate::types::{ConnectRequest, ConnectResponse, RevokeRequest};
+use crate::database::connect::{ConnectError, PinnedDroneError};
+use crate::types::{
+    BatchConnectRequest, BatchConnectResponse, ConnectRequest, ConnectResponse, RevokeRequest,
+    ScheduleDryRunResponse, SpawnConfig, MAX_BATCH_CONNECT_COUNT,
+};
 use axum::{extract::State, response::Response, Json};
 use reqwest::StatusCode;
 
-fn connect_error_to_response(connect_error: &ConnectError) -> Response {
+/// Returns the HTTP status, user-facing message, and error kind to report a connect error with.
+fn connect_error_details(connect_error: &ConnectError) -> (StatusCode, String, ApiErrorKind) {
     match connect_error {
-        ConnectError::FailedToAcquireKey => err_to_response(
-            connect_error,
+        ConnectError::FailedToAcquireKey => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to acquire lock.",
+            "Failed to acquire lock.".to_string(),
             ApiErrorKind::FailedToAcquireKey,
         ),
-        ConnectError::KeyUnheldNoSpawnConfig => err_to_response(
-            connect_error,
+        ConnectError::KeyUnheldNoSpawnConfig => (
             StatusCode::CONFLICT,
-            "Lock is unheld but no spawn config was provided.",
+            "Lock is unheld but no spawn config was provided.".to_string(),
             ApiErrorKind::KeyUnheldNoSpawnConfig,
         ),
-        ConnectError::KeyHeldUnhealthy => err_to_response(
-            connect_error,
+        ConnectError::KeyHeldUnhealthy => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            "Lock is held but unhealthy.",
+            "Lock is held but unhealthy.".to_string(),
             ApiErrorKind::KeyHeldUnhealthy,
         ),
-        ConnectError::KeyHeld { .. } => err_to_response(
-            connect_error,
+        ConnectError::KeyHeld { .. } => (
             StatusCode::CONFLICT,
-            "Lock is held but tag does not match.",
+            "Lock is held but tag does not match.".to_string(),
             ApiErrorKind::KeyHeld,
         ),
-        ConnectError::NoDroneAvailable => err_to_response(
-            connect_error,
+        ConnectError::NoDroneAvailable | ConnectError::DroneAtCapacity => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            "No active drone available.",
+            "No active drone available.".to_string(),
             ApiErrorKind::NoDroneAvailable,
         ),
-        ConnectError::FailedToRemoveKey => err_to_response(
-            connect_error,
+        ConnectError::PinnedDroneUnavailable { reason, .. } => (
+            match reason {
+                PinnedDroneError::Unknown | PinnedDroneError::WrongPool => StatusCode::BAD_REQUEST,
+                PinnedDroneError::Draining
+                | PinnedDroneError::Unhealthy
+                | PinnedDroneError::AtCapacity => StatusCode::CONFLICT,
+            },
+            connect_error.to_string(),
+            ApiErrorKind::PinnedDroneUnavailable,
+        ),
+        ConnectError::FailedToRemoveKey => (
             StatusCode::CONFLICT,
-            "Failed to remove lock.",
+            "Failed to remove lock.".to_string(),
             ApiErrorKind::FailedToRemoveKey,
         ),
-        ConnectError::DatabaseError(_) | ConnectError::Serialization(_) => err_to_response(
-            connect_error,
+        ConnectError::DatabaseError(_) | ConnectError::Serialization(_) => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            "Internal error.",
+            "Internal error.".to_string(),
             ApiErrorKind::Other,
         ),
-        ConnectError::NoClusterProvided => err_to_response(
-            connect_error,
+        ConnectError::NoClusterProvided => (
             StatusCode::BAD_REQUEST,
-            "No cluster provided, and no default cluster for this controller.",
+            "No cluster provided, and no default cluster for this controller.".to_string(),
             ApiErrorKind::NoClusterProvided,
         ),
-        ConnectError::Other(_) => err_to_response(
-            connect_error,
+        ConnectError::Other(_) => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            "Internal error.",
+            "Internal error.".to_string(),
             ApiErrorKind::Other,
         ),
     }
 }
 
+fn connect_error_to_response(connect_error: &ConnectError) -> Response {
+    let (status, message, kind) = connect_error_details(connect_error);
+    err_to_response(connect_error, status, &message, kind)
+}
+
+fn connect_error_to_api_error(connect_error: &ConnectError) -> ApiError {
+    let (status, message, kind) = connect_error_details(connect_error);
+    to_api_error(connect_error, status, &message, kind)
+}
+
 pub async fn handle_connect(
     State(controller): State<Controller>,
     Json(request): Json<ConnectRequest>,
@@ -76,6 +91,74 @@ pub async fn handle_connect(
     Ok(Json(response))
 }
 
+/// Spawns `count` backends from the same connect request. Each spawn is placed
+/// independently, and a failure of one does not stop the others.
+pub async fn handle_connect_batch(
+    State(controller): State<Controller>,
+    Json(batch): Json<BatchConnectRequest>,
+) -> Result<Json<BatchConnectResponse>, Response> {
+    if batch.count == 0 || batch.count > MAX_BATCH_CONNECT_COUNT {
+        return Err(err_to_response(
+            batch.count,
+            StatusCode::BAD_REQUEST,
+            &format!("Batch count must be between 1 and {MAX_BATCH_CONNECT_COUNT}."),
+            ApiErrorKind::InvalidBatchRequest,
+        ));
+    }
+
+    let Some(spawn_config) = &batch.request.spawn_config else {
+        return Err(err_to_response(
+            "Invalid batch request.",
+            StatusCode::BAD_REQUEST,
+            "Batch requests must include a spawn config.",
+            ApiErrorKind::InvalidBatchRequest,
+        ));
+    };
+
+    if batch.request.key.is_some() || spawn_config.id.is_some() {
+        return Err(err_to_response(
+            "Invalid batch request.",
+            StatusCode::BAD_REQUEST,
+            "Batch requests must not include a key or backend ID.",
+            ApiErrorKind::InvalidBatchRequest,
+        ));
+    }
+
+    // Spawns in a batch run one after another, so waiting for a drone on each of them
+    // could hold the request open for up to count * MAX_SPAWN_WAIT_SECONDS.
+    if spawn_config.max_wait_seconds.is_some() {
+        return Err(err_to_response(
+            "Invalid batch request.",
+            StatusCode::BAD_REQUEST,
+            "Batch requests must not include max_wait_seconds.",
+            ApiErrorKind::InvalidBatchRequest,
+        ));
+    }
+
+    let mut results = Vec::with_capacity(batch.count as usize);
+    for _ in 0..batch.count {
+        let result = controller
+            .connect(&batch.request)
+            .await
+            .map_err(|e| connect_error_to_api_error(&e));
+        results.push(result);
+    }
+
+    Ok(Json(BatchConnectResponse { results }))
+}
+
+/// Reports where a spawn would be placed, and why, without creating a backend.
+pub async fn handle_schedule_dry_run(
+    State(controller): State<Controller>,
+    Json(spawn_config): Json<SpawnConfig>,
+) -> Result<Json<ScheduleDryRunResponse>, Response> {
+    let response = controller
+        .schedule_dry_run(&spawn_config)
+        .await
+        .map_err(|e| connect_error_to_response(&e))?;
+    Ok(Json(response))
+}
+
 // TODO: Make proxies aware when a token is revoked, because they cache the
 // token->backend mapping. This will probably require a larger re-thinking of
 // how data is synchronized between the controller and proxies. Eventually we
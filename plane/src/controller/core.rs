@@ -1,12 +1,18 @@
+use super::{
+    metrics::ControllerMetrics,
+    webhook::{WebhookConfig, WebhookNotifier},
+};
 use crate::{
     client::PlaneClient,
     database::{connect::ConnectError, PlaneDatabase},
     names::{AnyNodeName, ControllerName},
     typed_socket::Handshake,
-    types::{ClusterName, ConnectRequest, ConnectResponse, NodeId},
+    types::{
+        ClusterName, ConnectRequest, ConnectResponse, NodeId, ScheduleDryRunResponse, SpawnConfig,
+    },
 };
 use chrono::{DateTime, Utc};
-use std::net::IpAddr;
+use std::{net::IpAddr, sync::Arc};
 use url::Url;
 
 #[derive(Clone)]
@@ -15,6 +21,7 @@ pub struct Controller {
     pub id: ControllerName,
     pub client: PlaneClient,
     pub default_cluster: Option<ClusterName>,
+    pub metrics: Arc<ControllerMetrics>,
 }
 
 pub struct NodeHandle {
@@ -77,14 +84,20 @@ impl Controller {
         id: ControllerName,
         controller_url: Url,
         default_cluster: Option<ClusterName>,
+        webhook: Option<WebhookConfig>,
     ) -> Self {
         let client = PlaneClient::new(controller_url);
+        let db = match webhook {
+            Some(config) => db.with_webhook(Arc::new(WebhookNotifier::new(config))),
+            None => db,
+        };
 
         Self {
             db,
             id,
             client,
             default_cluster,
+            metrics: Arc::default(),
         }
     }
 
@@ -99,4 +112,13 @@ impl Controller {
 
         Ok(response)
     }
+
+    pub async fn schedule_dry_run(
+        &self,
+        spawn_config: &SpawnConfig,
+    ) -> Result<ScheduleDryRunResponse, ConnectError> {
+        self.db
+            .schedule_dry_run(self.default_cluster.as_ref(), spawn_config)
+            .await
+    }
 }
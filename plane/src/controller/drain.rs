@@ -43,3 +43,37 @@ pub async fn handle_drain(
     let result = drain(&controller, &cluster, &drone).await?;
     Ok(Json(result))
 }
+
+async fn undrain(
+    controller: &Controller,
+    cluster: &ClusterName,
+    drone: &DroneName,
+) -> Result<DrainResult, Response> {
+    let drone_id = controller
+        .db
+        .node()
+        .get_id(cluster, drone)
+        .await
+        .or_internal_error("Database error")?
+        .or_not_found("Drone does not exist")?;
+
+    let updated = controller
+        .db
+        .drone()
+        .undrain(drone_id)
+        .await
+        .or_internal_error("Database error")?;
+
+    Ok(DrainResult { updated })
+}
+
+/// Reverses a drain, so that new backends may be scheduled on the drone again. Like
+/// draining, this only touches controller state, so it works even if the drone is
+/// not connected.
+pub async fn handle_undrain(
+    Path((cluster, drone)): Path<(ClusterName, DroneName)>,
+    State(controller): State<Controller>,
+) -> Result<Json<DrainResult>, Response> {
+    let result = undrain(&controller, &cluster, &drone).await?;
+    Ok(Json(result))
+}
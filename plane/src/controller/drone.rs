@@ -26,13 +26,16 @@ use axum::{
 use serde::Deserialize;
 use std::{
     net::{IpAddr, SocketAddr},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use valuable::Valuable;
 
 #[derive(Deserialize)]
 pub struct DroneSocketQuery {
     pool: Option<DronePoolName>,
+
+    /// The maximum number of non-terminated backends the drone will accept, if limited.
+    max_backends: Option<u32>,
 }
 
 pub async fn handle_message_from_drone(
@@ -55,11 +58,13 @@ pub async fn handle_message_from_drone(
         MessageFromDrone::BackendEvent(backend_event) => {
             tracing::info!(event = backend_event.as_value(), "Received backend event");
 
+            let start = Instant::now();
             controller
                 .db
                 .backend()
                 .update_state(&backend_event.backend_id, backend_event.state)
                 .await?;
+            controller.metrics.record_state_message(start.elapsed());
 
             sender.send(MessageToDrone::AckEvent {
                 event_id: backend_event.event_id,
@@ -167,6 +172,7 @@ pub async fn drone_socket_inner(
     controller: Controller,
     ip: IpAddr,
     pool: DronePoolName,
+    max_backends: Option<u32>,
 ) -> anyhow::Result<()> {
     let mut socket = new_server(ws, controller.id.to_string()).await?;
 
@@ -181,8 +187,9 @@ pub async fn drone_socket_inner(
     controller
         .db
         .drone()
-        .register_drone(drone_id, true, pool)
+        .register_drone(drone_id, true, pool, max_backends)
         .await?;
+    tracing::info!(%drone_id, ?max_backends, "Registered drone.");
 
     let mut backend_actions: Subscription<BackendActionMessage> =
         controller.db.subscribe_with_key(&drone_id.to_string());
@@ -238,8 +245,9 @@ pub async fn drone_socket(
     controller: Controller,
     ip: IpAddr,
     pool: DronePoolName,
+    max_backends: Option<u32>,
 ) {
-    if let Err(err) = drone_socket_inner(cluster, ws, controller, ip, pool).await {
+    if let Err(err) = drone_socket_inner(cluster, ws, controller, ip, pool, max_backends).await {
         tracing::error!(?err, "Drone socket error");
     }
 }
@@ -257,6 +265,9 @@ pub async fn handle_drone_socket(
         ApiErrorKind::InvalidClusterName,
     )?;
     let pool = query.pool.unwrap_or_default();
+    let max_backends = query.max_backends;
     let ip = connect_info.0.ip();
-    Ok(ws.on_upgrade(move |socket| drone_socket(cluster, socket, controller, ip, pool)))
+    Ok(ws.on_upgrade(move |socket| {
+        drone_socket(cluster, socket, controller, ip, pool, max_backends)
+    }))
 }
@@ -17,6 +17,8 @@ pub enum ApiErrorKind {
     KeyHeldUnhealthy,
     KeyHeld,
     NoDroneAvailable,
+    PinnedDroneUnavailable,
+    InvalidBatchRequest,
     FailedToRemoveKey,
     DatabaseError,
     NoClusterProvided,
@@ -38,12 +40,14 @@ impl Display for ApiError {
     }
 }
 
-pub fn err_to_response<E: Debug>(
+/// Logs an error and returns the API error to report it to the client with. The error is
+/// logged with the same ID that is returned, for correlating the two.
+pub fn to_api_error<E: Debug>(
     error: E,
     status: StatusCode,
     user_message: &str,
     code: ApiErrorKind,
-) -> Response {
+) -> ApiError {
     let err_id = random_string();
 
     if status.is_server_error() {
@@ -60,12 +64,20 @@ pub fn err_to_response<E: Debug>(
         );
     }
 
-    let result = ApiError {
-        id: err_id.clone(),
+    ApiError {
+        id: err_id,
         message: user_message.to_string(),
         kind: code,
-    };
+    }
+}
 
+pub fn err_to_response<E: Debug>(
+    error: E,
+    status: StatusCode,
+    user_message: &str,
+    code: ApiErrorKind,
+) -> Response {
+    let result = to_api_error(error, status, user_message, code);
     (status, Json(result)).into_response()
 }
 
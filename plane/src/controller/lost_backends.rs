@@ -0,0 +1,127 @@
+use crate::{
+    client::PlaneClient,
+    database::{connect::RetryLineage, PlaneDatabase},
+    heartbeat_consts::ASSUME_LOST_SECONDS,
+    names::BackendName,
+    types::{ClusterName, ConnectRequest},
+};
+use anyhow::Result;
+use std::time::Duration;
+use valuable::Valuable;
+
+/// How often each controller checks for lost backends.
+const LOST_BACKEND_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A connect request is rescheduled at most this many times, so that a backend
+/// which takes its drone down with it does not cycle through the whole cluster.
+pub const MAX_RESCHEDULE_ATTEMPTS: i32 = 3;
+
+/// Marks backends that were not yet ready on drones that have not been heard from in
+/// `lost_after` as lost. Those spawned with `reschedule` set are replaced by a new
+/// backend on another drone.
+pub async fn handle_lost_backends(
+    db: &PlaneDatabase,
+    client: &PlaneClient,
+    default_cluster: Option<&ClusterName>,
+    lost_after: chrono::Duration,
+) -> Result<()> {
+    for lost in db.backend().lost_backends(lost_after).await? {
+        // Only one controller wins the state update, so only one reschedules.
+        if !db
+            .backend()
+            .update_state(&lost.backend_id, lost.state.to_lost())
+            .await?
+        {
+            continue;
+        }
+
+        tracing::warn!(
+            backend = lost.backend_id.as_value(),
+            "Marked backend as lost."
+        );
+
+        let Some(request) = lost.reschedule_request else {
+            continue;
+        };
+
+        if lost.retry_count >= MAX_RESCHEDULE_ATTEMPTS {
+            tracing::warn!(
+                backend = lost.backend_id.as_value(),
+                retry_count = lost.retry_count,
+                "Not rescheduling lost backend, retry limit reached."
+            );
+            continue;
+        }
+
+        reschedule(
+            db,
+            client,
+            default_cluster,
+            &lost.backend_id,
+            request,
+            lost.retry_count,
+        )
+        .await;
+    }
+
+    Ok(())
+}
+
+async fn reschedule(
+    db: &PlaneDatabase,
+    client: &PlaneClient,
+    default_cluster: Option<&ClusterName>,
+    backend_id: &BackendName,
+    mut request: ConnectRequest,
+    retry_count: i32,
+) {
+    if let Some(spawn_config) = &mut request.spawn_config {
+        // The ID belongs to the lost backend, and a pinned drone is the one that was lost.
+        spawn_config.id = None;
+        spawn_config.drone = None;
+    }
+
+    let lineage = RetryLineage {
+        retry_of: backend_id.clone(),
+        retry_count: retry_count + 1,
+    };
+
+    match db
+        .reschedule(default_cluster, &request, client, &lineage)
+        .await
+    {
+        Ok(response) => {
+            tracing::info!(
+                backend = backend_id.as_value(),
+                replacement = response.backend_id.as_value(),
+                "Rescheduled lost backend."
+            );
+        }
+        Err(err) => {
+            tracing::error!(
+                ?err,
+                backend = backend_id.as_value(),
+                "Failed to reschedule lost backend."
+            );
+        }
+    }
+}
+
+pub async fn run_lost_backend_loop(
+    db: PlaneDatabase,
+    client: PlaneClient,
+    default_cluster: Option<ClusterName>,
+) {
+    let lost_after =
+        chrono::Duration::try_seconds(ASSUME_LOST_SECONDS).expect("valid constant duration");
+
+    loop {
+        if let Err(err) =
+            handle_lost_backends(&db, &client, default_cluster.as_ref(), lost_after).await
+        {
+            tracing::error!(?err, "Error handling lost backends.");
+        }
+
+        tokio::time::sleep(LOST_BACKEND_CHECK_INTERVAL).await;
+    }
+}
@@ -0,0 +1,213 @@
+use super::{core::Controller, error::IntoApiError};
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Counters local to this controller process. Unlike the gauges computed from the
+/// database, these only reflect work done by the controller being scraped.
+#[derive(Default)]
+pub struct ControllerMetrics {
+    state_messages: AtomicU64,
+    state_apply_micros: AtomicU64,
+}
+
+impl ControllerMetrics {
+    /// Records that a backend state message from a drone was processed, and how long
+    /// it took to apply to the database.
+    pub fn record_state_message(&self, apply_time: Duration) {
+        self.state_messages.fetch_add(1, Ordering::Relaxed);
+        self.state_apply_micros
+            .fetch_add(apply_time.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum MetricKind {
+    Counter,
+    Gauge,
+    Summary,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Summary => "summary",
+        }
+    }
+}
+
+/// One line of a metric. `suffix` is appended to the metric name, e.g. `_sum` for
+/// one part of a summary.
+struct Sample {
+    suffix: &'static str,
+    labels: Vec<(&'static str, String)>,
+    value: f64,
+}
+
+impl Sample {
+    fn new(value: f64) -> Self {
+        Self {
+            suffix: "",
+            labels: Vec::new(),
+            value,
+        }
+    }
+
+    fn label(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.labels.push((name, value.to_string()));
+        self
+    }
+
+    fn suffix(mut self, suffix: &'static str) -> Self {
+        self.suffix = suffix;
+        self
+    }
+}
+
+/// Appends a metric's HELP and TYPE lines, followed by its samples, to `body`.
+fn write_metric(
+    body: &mut String,
+    name: &str,
+    kind: MetricKind,
+    help: &str,
+    samples: impl IntoIterator<Item = Sample>,
+) {
+    // Writing to a String is infallible, so the results of write! are ignored.
+    let _ = writeln!(body, "# HELP {} {}", name, help);
+    let _ = writeln!(body, "# TYPE {} {}", name, kind.as_str());
+    for sample in samples {
+        let _ = write!(body, "{}{}", name, sample.suffix);
+        if !sample.labels.is_empty() {
+            let labels: Vec<String> = sample
+                .labels
+                .iter()
+                .map(|(name, value)| format!("{}=\"{}\"", name, value))
+                .collect();
+            let _ = write!(body, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(body, " {}", sample.value);
+    }
+}
+
+/// Renders cluster-wide gauges in the Prometheus text exposition format.
+/// Values are computed from the database on each scrape, so every controller
+/// reports the same numbers.
+pub async fn handle_metrics(State(controller): State<Controller>) -> Result<Response, Response> {
+    let metrics = controller
+        .db
+        .cluster()
+        .metrics()
+        .await
+        .or_internal_error("Database error")?;
+
+    let mut body = String::new();
+
+    write_metric(
+        &mut body,
+        "plane_backends",
+        MetricKind::Gauge,
+        "Number of non-terminated backends by status.",
+        metrics.backends.iter().map(|backends| {
+            Sample::new(backends.count as f64)
+                .label("cluster", &backends.cluster)
+                .label("status", backends.status)
+        }),
+    );
+
+    write_metric(
+        &mut body,
+        "plane_drones",
+        MetricKind::Gauge,
+        "Number of drones connected to a controller.",
+        metrics
+            .drones
+            .iter()
+            .map(|drones| Sample::new(drones.count as f64).label("cluster", &drones.cluster)),
+    );
+
+    write_metric(
+        &mut body,
+        "plane_txt_records",
+        MetricKind::Gauge,
+        "Number of unexpired ACME DNS-01 TXT records.",
+        metrics.txt_records.iter().map(|txt_records| {
+            Sample::new(txt_records.count as f64).label("cluster", &txt_records.cluster)
+        }),
+    );
+
+    let state_messages = controller.metrics.state_messages.load(Ordering::Relaxed);
+    let state_apply_micros = controller
+        .metrics
+        .state_apply_micros
+        .load(Ordering::Relaxed);
+
+    write_metric(
+        &mut body,
+        "plane_backend_state_messages_total",
+        MetricKind::Counter,
+        "Number of backend state messages from drones processed by this controller.",
+        [Sample::new(state_messages as f64)],
+    );
+
+    write_metric(
+        &mut body,
+        "plane_backend_state_apply_seconds",
+        MetricKind::Summary,
+        "Time spent applying backend state messages from drones.",
+        [
+            Sample::new(state_apply_micros as f64 / 1_000_000.0).suffix("_sum"),
+            Sample::new(state_messages as f64).suffix("_count"),
+        ],
+    );
+
+    write_metric(
+        &mut body,
+        "plane_malformed_notifications_total",
+        MetricKind::Counter,
+        "Number of notifications received by this controller with a malformed payload.",
+        controller
+            .db
+            .malformed_notifications()
+            .into_iter()
+            .map(|(kind, count)| Sample::new(count as f64).label("kind", kind)),
+    );
+
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_metric_renders_labels_and_suffixes() {
+        let mut body = String::new();
+        write_metric(
+            &mut body,
+            "plane_example",
+            MetricKind::Summary,
+            "An example.",
+            [
+                Sample::new(1.5).suffix("_sum").label("cluster", "a.test"),
+                Sample::new(3.0).suffix("_count"),
+            ],
+        );
+
+        assert_eq!(
+            body,
+            "# HELP plane_example An example.\n\
+             # TYPE plane_example summary\n\
+             plane_example_sum{cluster=\"a.test\"} 1.5\n\
+             plane_example_count 3\n"
+        );
+    }
+}
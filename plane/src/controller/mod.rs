@@ -1,10 +1,11 @@
 use self::{
     backend_state::{handle_backend_status, handle_backend_status_stream},
     cluster_state::handle_cluster_state,
-    connect::handle_revoke,
+    connect::{handle_connect_batch, handle_revoke, handle_schedule_dry_run},
     dns::handle_dns_socket,
-    drain::handle_drain,
+    drain::{handle_drain, handle_undrain},
     error::IntoApiError,
+    metrics::handle_metrics,
     proxy::handle_proxy_socket,
 };
 use crate::{
@@ -44,6 +45,7 @@ use tower_http::{
 };
 use tracing::Level;
 use url::Url;
+use webhook::WebhookConfig;
 
 mod backend_state;
 mod cluster_state;
@@ -55,8 +57,11 @@ mod drain;
 mod drone;
 pub mod error;
 mod forward_auth;
+pub mod lost_backends;
+mod metrics;
 mod proxy;
 mod terminate;
+pub mod webhook;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StatusResponse {
@@ -148,6 +153,7 @@ pub struct ControllerServer {
     // when gracefully terminating.
     server_handle: Option<JoinHandle<hyper::Result<()>>>,
     _cleanup_handle: GuardHandle,
+    _lost_backend_handle: GuardHandle,
 }
 
 impl ControllerServer {
@@ -171,6 +177,7 @@ impl ControllerServer {
             config.cleanup_min_age_days,
             config.cleanup_batch_size,
             config.forward_auth,
+            config.webhook,
         )
         .await
     }
@@ -185,6 +192,7 @@ impl ControllerServer {
         cleanup_min_age_days: Option<i32>,
         cleanup_batch_size: Option<i32>,
         forward_auth: Option<Url>,
+        webhook: Option<WebhookConfig>,
     ) -> Result<Self> {
         let bind_addr = listener.local_addr()?;
 
@@ -199,8 +207,26 @@ impl ControllerServer {
         let (graceful_terminate_sender, graceful_terminate_receiver) =
             tokio::sync::oneshot::channel::<()>();
 
-        let controller =
-            Controller::new(db.clone(), id.clone(), controller_url, default_cluster).await;
+        let controller = Controller::new(
+            db.clone(),
+            id.clone(),
+            controller_url,
+            default_cluster,
+            webhook,
+        )
+        .await;
+
+        let lost_backend_handle = {
+            let controller = controller.clone();
+            GuardHandle::new(async move {
+                lost_backends::run_lost_backend_loop(
+                    controller.db,
+                    controller.client,
+                    controller.default_cluster,
+                )
+                .await
+            })
+        };
 
         let trace_layer = TraceLayer::new_for_http()
             .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
@@ -218,11 +244,15 @@ impl ControllerServer {
         let mut control_routes = Router::new()
             .route("/status", get(status))
             .route("/c/:cluster/state", get(handle_cluster_state))
+            .route("/metrics", get(handle_metrics))
             .route("/c/:cluster/drone-socket", get(handle_drone_socket))
             .route("/c/:cluster/proxy-socket", get(handle_proxy_socket))
             .route("/dns-socket", get(handle_dns_socket))
             .route("/connect", post(handle_connect))
+            .route("/connect-batch", post(handle_connect_batch))
+            .route("/schedule-dry-run", post(handle_schedule_dry_run))
             .route("/c/:cluster/d/:drone/drain", post(handle_drain))
+            .route("/c/:cluster/d/:drone/undrain", post(handle_undrain))
             .route(
                 "/b/:backend/soft-terminate",
                 post(terminate::handle_soft_terminate),
@@ -280,6 +310,7 @@ impl ControllerServer {
             controller_id: id,
             bind_addr,
             _cleanup_handle: cleanup_handle,
+            _lost_backend_handle: lost_backend_handle,
         })
     }
 
@@ -341,6 +372,9 @@ pub struct ControllerConfig {
     pub cleanup_min_age_days: Option<i32>,
     pub cleanup_batch_size: Option<i32>,
     pub forward_auth: Option<Url>,
+    /// If provided, backend state transitions are posted to this webhook.
+    #[serde(default)]
+    pub webhook: Option<WebhookConfig>,
 }
 
 pub async fn run_controller(config: ControllerConfig) -> Result<()> {
@@ -7,8 +7,8 @@ use crate::{
     },
     names::{BackendName, Name},
     protocol::{
-        CertManagerRequest, CertManagerResponse, MessageFromProxy, MessageToProxy,
-        RouteInfoRequest, RouteInfoResponse,
+        BackendConnectionStats, CertManagerRequest, CertManagerResponse, MessageFromProxy,
+        MessageToProxy, RouteInfoRequest, RouteInfoResponse,
     },
     typed_socket::{server::new_server, TypedSocket},
     types::{BackendState, BearerToken, ClusterName, NodeId},
@@ -188,6 +188,26 @@ pub async fn handle_message_from_proxy(
                 }
             }
         }
+        MessageFromProxy::ConnectionStats(BackendConnectionStats {
+            backend,
+            active_connections,
+        }) => {
+            // The KeepAlive sent alongside this message takes care of telling the proxy
+            // about backends that no longer exist.
+            if let Err(err) = controller
+                .db
+                .backend()
+                .update_active_connections(&backend, node_id, active_connections)
+                .await
+            {
+                tracing::error!(
+                    ?err,
+                    ?backend,
+                    ?node_id,
+                    "Unhandled database error updating active connections"
+                );
+            }
+        }
         MessageFromProxy::CertManagerRequest(cert_manager_request) => {
             let response = match cert_manager_request {
                 CertManagerRequest::CertLeaseRequest => {
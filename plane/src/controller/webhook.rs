@@ -0,0 +1,178 @@
+use crate::{
+    log_types::LoggableTime,
+    names::BackendName,
+    types::{BackendState, ClusterName},
+    util::GuardHandle,
+};
+use chrono::Utc;
+use futures_util::StreamExt;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use url::Url;
+
+/// Header containing the hex-encoded HMAC-SHA256 of the request body, prefixed with `sha256=`.
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-plane-signature";
+
+/// Number of times delivery of a notification is attempted before it is given up on.
+const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+
+/// Delay before the first retry. Doubles with each subsequent retry.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of notifications that can be waiting for delivery. Notifications sent while
+/// the queue is full are dropped (and logged), so that an unreachable endpoint cannot
+/// grow controller memory without bound.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Number of notifications delivered at once, so that one slow delivery does not hold
+/// up the rest.
+const MAX_CONCURRENT_DELIVERIES: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// URL to POST backend state transitions to.
+    pub url: Url,
+
+    /// If provided, each request is signed with this secret (see WEBHOOK_SIGNATURE_HEADER).
+    pub secret: Option<String>,
+}
+
+/// Body of a webhook request, sent each time this controller applies a backend state change.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackendStateWebhookPayload {
+    pub cluster: ClusterName,
+    pub backend_id: BackendName,
+    pub old_state: BackendState,
+    pub new_state: BackendState,
+    pub timestamp: LoggableTime,
+}
+
+/// Returns the value of the signature header for the given body.
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, body);
+    format!("sha256={}", data_encoding::HEXLOWER.encode(tag.as_ref()))
+}
+
+/// Delivers webhook notifications in the background. Notifications are taken from the
+/// queue in the order they were sent, but since several are delivered concurrently,
+/// receivers should order them by timestamp.
+pub struct WebhookNotifier {
+    sender: mpsc::Sender<BackendStateWebhookPayload>,
+    _handle: GuardHandle,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Self {
+        let (sender, receiver) = mpsc::channel::<BackendStateWebhookPayload>(QUEUE_CAPACITY);
+        let client = reqwest::Client::new();
+
+        let handle = GuardHandle::new(async move {
+            ReceiverStream::new(receiver)
+                .for_each_concurrent(MAX_CONCURRENT_DELIVERIES, |payload| {
+                    let client = &client;
+                    let config = &config;
+                    async move { deliver(client, config, &payload).await }
+                })
+                .await;
+        });
+
+        Self {
+            sender,
+            _handle: handle,
+        }
+    }
+
+    pub fn notify(
+        &self,
+        cluster: ClusterName,
+        backend_id: BackendName,
+        old_state: BackendState,
+        new_state: BackendState,
+    ) {
+        let payload = BackendStateWebhookPayload {
+            cluster,
+            backend_id,
+            old_state,
+            new_state,
+            timestamp: LoggableTime(Utc::now()),
+        };
+
+        match self.sender.try_send(payload) {
+            Ok(()) => (),
+            Err(mpsc::error::TrySendError::Full(payload)) => {
+                log_dead_letter(&payload, "Webhook queue is full.");
+            }
+            Err(mpsc::error::TrySendError::Closed(payload)) => {
+                log_dead_letter(&payload, "Webhook notifier is no longer running.");
+            }
+        }
+    }
+}
+
+async fn deliver(
+    client: &reqwest::Client,
+    config: &WebhookConfig,
+    payload: &BackendStateWebhookPayload,
+) {
+    let body = serde_json::to_vec(payload).expect("Webhook payload is always serializable.");
+    let signature = config
+        .secret
+        .as_ref()
+        .map(|secret| sign_payload(secret, &body));
+
+    let mut delay = INITIAL_RETRY_DELAY;
+    for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+        let mut request = client
+            .post(config.url.clone())
+            .timeout(DELIVERY_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(WEBHOOK_SIGNATURE_HEADER, signature);
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => {
+                tracing::warn!(status = %response.status(), attempt, "Webhook request rejected.");
+            }
+            Err(err) => {
+                tracing::warn!(?err, attempt, "Webhook request failed.");
+            }
+        }
+
+        if attempt < MAX_DELIVERY_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    log_dead_letter(payload, "Giving up on webhook delivery.");
+}
+
+/// Logs the full payload of a notification that will not be delivered, so that it can
+/// be recovered and replayed.
+fn log_dead_letter(payload: &BackendStateWebhookPayload, reason: &str) {
+    let payload = serde_json::to_string(payload).expect("Webhook payload is always serializable.");
+    tracing::error!(%payload, reason, "Dropping webhook notification.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_is_hex_hmac_sha256() {
+        // Test vector from RFC 4231, test case 2.
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -1,11 +1,18 @@
 use crate::types::{ClusterName, NodeId};
 use sqlx::query;
-use sqlx::PgPool;
+use sqlx::{postgres::types::PgInterval, PgPool};
+use std::time::Duration;
+
+/// How long after a lease is acquired its TXT record is served. A proxy normally
+/// releases the lease (removing the record) as soon as its order completes, so this
+/// only applies to records left behind by a proxy that went away mid-order.
+pub(super) const TXT_RECORD_EXPIRY: Duration = Duration::from_secs(10 * 60);
 
 pub struct AcmeDatabase<'a> {
     pool: &'a PgPool,
 }
 
+// TXT records are keyed by the cluster's host, since DNS queries carry no port.
 impl<'a> AcmeDatabase<'a> {
     pub fn new(pool: &'a PgPool) -> Self {
         Self { pool }
@@ -26,7 +33,7 @@ impl<'a> AcmeDatabase<'a> {
                 leased_by = $2
             where (acme_txt_entries.leased_at + interval '1 minute') < now()
             "#,
-            cluster.to_string(),
+            cluster.host(),
             proxy.as_i32(),
         )
         .execute(self.pool)
@@ -48,7 +55,7 @@ impl<'a> AcmeDatabase<'a> {
             where cluster = $1
             and leased_by = $2
             "#,
-            cluster.to_string(),
+            cluster.host(),
             proxy.as_i32(),
             txt_value,
         )
@@ -67,8 +74,10 @@ impl<'a> AcmeDatabase<'a> {
             select txt_value
             from acme_txt_entries
             where cluster = $1
+            and leased_at + $2 > now()
             "#,
-            cluster.to_string(),
+            cluster.host(),
+            PgInterval::try_from(TXT_RECORD_EXPIRY).expect("valid interval"),
         )
         .fetch_optional(self.pool)
         .await?;
@@ -87,7 +96,7 @@ impl<'a> AcmeDatabase<'a> {
             where cluster = $1
             and leased_by = $2
             "#,
-            cluster.to_string(),
+            cluster.host(),
             proxy.as_i32(),
         )
         .execute(self.pool)
@@ -95,4 +104,19 @@ impl<'a> AcmeDatabase<'a> {
 
         Ok(())
     }
+
+    /// Removes TXT record leases that have outlived TXT_RECORD_EXPIRY.
+    pub async fn clean_up_expired_txt_records(&self) -> sqlx::Result<u64> {
+        let result = query!(
+            r#"
+            delete from acme_txt_entries
+            where leased_at + $1 < now()
+            "#,
+            PgInterval::try_from(TXT_RECORD_EXPIRY).expect("valid interval"),
+        )
+        .execute(self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
 }
@@ -1,20 +1,23 @@
 use super::{
+    drone::emit_if_drained,
     subscribe::{emit_ephemeral_with_key, emit_with_key},
     PlaneDatabase,
 };
 use crate::{
+    heartbeat_consts::UNHEALTHY_SECONDS,
     log_types::BackendAddr,
     names::{BackendActionName, BackendName},
     protocol::{BackendAction, RouteInfo},
     types::{
-        backend_state::BackendStatusStreamEntry, BackendState, BackendStatus, BearerToken,
-        ClusterName, NodeId, SecretToken, Subdomain,
+        backend_state::{BackendStatusHistory, BackendStatusStreamEntry},
+        BackendState, BackendStatus, BearerToken, ClusterName, ConnectRequest, NodeId, SecretToken,
+        Subdomain,
     },
 };
 use chrono::{DateTime, Utc};
 use futures_util::Stream;
 use serde::{Deserialize, Serialize};
-use sqlx::PgConnection;
+use sqlx::{postgres::types::PgInterval, PgConnection};
 use std::{fmt::Debug, net::SocketAddr, str::FromStr};
 use valuable::Valuable;
 
@@ -138,6 +141,36 @@ impl<'a> BackendDatabase<'a> {
         Ok(stream)
     }
 
+    /// Returns the full history of status transitions for a backend.
+    pub async fn status_history(
+        &self,
+        backend: &BackendName,
+    ) -> sqlx::Result<BackendStatusHistory> {
+        let result = sqlx::query!(
+            r#"
+            select
+                created_at,
+                state
+            from backend_state
+            where backend_id = $1
+            order by id asc
+            "#,
+            backend.to_string(),
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        let mut transitions = Vec::with_capacity(result.len());
+        for row in result {
+            match serde_json::from_value::<BackendState>(row.state) {
+                Ok(state) => transitions.push((row.created_at, state.status())),
+                Err(e) => tracing::warn!(?e, "Invalid backend status"),
+            }
+        }
+
+        Ok(BackendStatusHistory::new(transitions))
+    }
+
     pub async fn backend(&self, backend_id: &BackendName) -> sqlx::Result<Option<BackendRow>> {
         let result = sqlx::query!(
             r#"
@@ -184,6 +217,17 @@ impl<'a> BackendDatabase<'a> {
         backend: &BackendName,
         new_state: BackendState,
     ) -> sqlx::Result<bool> {
+        Ok(self.apply_state(backend, new_state).await?.is_some())
+    }
+
+    /// Updates the state of a backend, unless it is already in the same or a later state.
+    /// If the update is applied, it is posted to the webhook, if one is configured, and
+    /// the backend's cluster and the state it replaced are returned.
+    pub async fn apply_state(
+        &self,
+        backend: &BackendName,
+        new_state: BackendState,
+    ) -> sqlx::Result<Option<AppliedState>> {
         let mut txn = self.db.pool.begin().await?;
 
         let new_status = new_state.status();
@@ -198,8 +242,15 @@ impl<'a> BackendDatabase<'a> {
                 last_status_number = $3,
                 cluster_address = $4,
                 state = $5
-            where id = $1
-            and (last_status_number < $3 or last_status_number is null)
+            from (
+                select id, state
+                from backend
+                where id = $1
+                for update
+            ) as previous
+            where backend.id = previous.id
+            and (backend.last_status_number < $3 or backend.last_status_number is null)
+            returning backend.drone_id, backend.cluster, previous.state as previous_state
             "#,
             backend.to_string(),
             new_status.to_string(),
@@ -208,10 +259,10 @@ impl<'a> BackendDatabase<'a> {
             serde_json::to_value(&new_state)
                 .expect("BackendState should always be JSON-serializable."),
         )
-        .execute(&mut *txn)
+        .fetch_optional(&mut *txn)
         .await?;
 
-        if result.rows_affected() == 0 {
+        let Some(result) = result else {
             let result = sqlx::query!(
                 r#"
                 select last_status
@@ -226,8 +277,8 @@ impl<'a> BackendDatabase<'a> {
             let last_status = result.map(|r| r.last_status);
 
             tracing::warn!(last_status, new_status=%new_status, backend=backend.as_value(), "Not updating backend status");
-            return Ok(false);
-        }
+            return Ok(None);
+        };
 
         // If the backend is terminated, we can delete its associated key.
         if matches!(new_state, BackendState::Terminated { .. }) {
@@ -244,9 +295,29 @@ impl<'a> BackendDatabase<'a> {
 
         emit_state_change(&mut txn, backend, &new_state).await?;
 
+        if matches!(new_state, BackendState::Terminated { .. }) {
+            emit_if_drained(&mut txn, NodeId::from(result.drone_id)).await?;
+        }
+
+        let applied = AppliedState {
+            cluster: ClusterName::from_str(&result.cluster)
+                .map_err(|_| sqlx::Error::Decode("Failed to decode cluster name.".into()))?,
+            previous_state: serde_json::from_value(result.previous_state)
+                .map_err(|_| sqlx::Error::Decode("Failed to decode backend state.".into()))?,
+        };
+
         txn.commit().await?;
 
-        Ok(true)
+        if let Some(webhook) = &self.db.webhook {
+            webhook.notify(
+                applied.cluster.clone(),
+                backend.clone(),
+                applied.previous_state.clone(),
+                new_state,
+            );
+        }
+
+        Ok(Some(applied))
     }
 
     pub async fn list_backends(&self) -> sqlx::Result<Vec<BackendRow>> {
@@ -453,6 +524,56 @@ impl<'a> BackendDatabase<'a> {
         Ok(true)
     }
 
+    /// Records the number of open connections to the backend through the given proxy.
+    /// Returns false if the backend does not exist.
+    pub async fn update_active_connections(
+        &self,
+        backend_id: &BackendName,
+        proxy: NodeId,
+        active_connections: u32,
+    ) -> sqlx::Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            insert into backend_proxy_connections (backend_id, proxy_id, active_connections, last_reported)
+            select id, $2, $3, now()
+            from backend
+            where id = $1
+            on conflict (backend_id, proxy_id) do update set
+                active_connections = $3,
+                last_reported = now()
+            "#,
+            backend_id.to_string(),
+            proxy.as_i32(),
+            active_connections as i32,
+        )
+        .execute(&self.db.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The number of open connections to the backend, summed across the proxies that
+    /// have reported a count within UNHEALTHY_SECONDS. Counts from proxies that have gone
+    /// quiet are ignored, since a proxy that goes away cannot report its connections
+    /// closing. Returns None if no proxy has reported a count in that time.
+    pub async fn active_connections(&self, backend_id: &BackendName) -> sqlx::Result<Option<u32>> {
+        let result = sqlx::query!(
+            r#"
+            select sum(active_connections) as "active_connections"
+            from backend_proxy_connections
+            where backend_id = $1
+            and now() - last_reported < $2
+            "#,
+            backend_id.to_string(),
+            PgInterval::try_from(std::time::Duration::from_secs(UNHEALTHY_SECONDS as _))
+                .expect("valid interval"),
+        )
+        .fetch_one(&self.db.pool)
+        .await?;
+
+        Ok(result.active_connections.map(|c| c as u32))
+    }
+
     pub async fn publish_metrics(&self, metrics: BackendMetricsMessage) -> sqlx::Result<()> {
         let mut txn = self.db.pool.begin().await?;
         emit_ephemeral_with_key(&mut txn, &metrics.backend_id.to_string(), &metrics).await?;
@@ -503,6 +624,53 @@ impl<'a> BackendDatabase<'a> {
         Ok(candidates)
     }
 
+    /// Returns backends that have not become ready, on drones that have not sent a
+    /// heartbeat within `lost_after`.
+    pub async fn lost_backends(
+        &self,
+        lost_after: chrono::Duration,
+    ) -> sqlx::Result<Vec<LostBackend>> {
+        let result = sqlx::query!(
+            r#"
+            select
+                backend.id,
+                backend.state,
+                backend.reschedule_request,
+                backend.retry_count
+            from backend
+            inner join drone on backend.drone_id = drone.id
+            where
+                backend.last_status_number < $1
+                and (drone.last_heartbeat is null or now() - drone.last_heartbeat > $2)
+            "#,
+            BackendStatus::Ready.as_int(),
+            PgInterval::try_from(lost_after.to_std().unwrap_or_default()).expect("valid interval"),
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        let mut lost = Vec::new();
+        for row in result {
+            let reschedule_request = match row.reschedule_request {
+                Some(request) => Some(serde_json::from_value(request).map_err(|_| {
+                    sqlx::Error::Decode("Failed to decode reschedule request.".into())
+                })?),
+                None => None,
+            };
+
+            lost.push(LostBackend {
+                backend_id: BackendName::try_from(row.id)
+                    .map_err(|_| sqlx::Error::Decode("Failed to decode backend name.".into()))?,
+                state: serde_json::from_value(row.state)
+                    .map_err(|_| sqlx::Error::Decode("Failed to decode backend state.".into()))?,
+                reschedule_request,
+                retry_count: row.retry_count,
+            });
+        }
+
+        Ok(lost)
+    }
+
     pub async fn cleanup(&self, min_age_days: i32, batch_size: i32) -> sqlx::Result<()> {
         tracing::info!("Cleaning up terminated backends.");
         let mut txn = self.db.pool.begin().await?;
@@ -569,6 +737,17 @@ impl<'a> BackendDatabase<'a> {
 
         let backend_state_deleted = backend_state_result.rows_affected();
 
+        let backend_proxy_connections_result = sqlx::query(
+            r#"
+            delete from backend_proxy_connections
+            where backend_proxy_connections.backend_id in (select id from deleted_backend);
+            "#,
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        let backend_proxy_connections_deleted = backend_proxy_connections_result.rows_affected();
+
         let backend_result = sqlx::query(
             r#"
             delete from backend
@@ -586,6 +765,7 @@ impl<'a> BackendDatabase<'a> {
             token_deleted,
             backend_action_deleted,
             backend_state_deleted,
+            backend_proxy_connections_deleted,
             backend_deleted,
             backend_key_deleted,
             "Finished cleanup."
@@ -595,6 +775,23 @@ impl<'a> BackendDatabase<'a> {
     }
 }
 
+/// The result of a state update that was applied.
+#[derive(Debug, Clone)]
+pub struct AppliedState {
+    pub cluster: ClusterName,
+    pub previous_state: BackendState,
+}
+
+/// A backend that had not become ready when its drone stopped sending heartbeats.
+#[derive(Debug, Clone)]
+pub struct LostBackend {
+    pub backend_id: BackendName,
+    pub state: BackendState,
+    /// The connect request to re-run, if the backend was spawned with `reschedule` set.
+    pub reschedule_request: Option<ConnectRequest>,
+    pub retry_count: i32,
+}
+
 #[derive(Debug, Clone)]
 pub struct TerminationCandidate {
     pub backend_id: BackendName,
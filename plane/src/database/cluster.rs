@@ -1,8 +1,85 @@
+use super::acme::TXT_RECORD_EXPIRY;
 use crate::{
     names::{AnyNodeName, ControllerName},
     types::{BackendStatus, ClusterName, ClusterState, DroneState, NodeState},
 };
-use sqlx::PgPool;
+use sqlx::{postgres::types::PgInterval, PgPool};
+use std::collections::BTreeMap;
+
+/// The number of backends in a cluster with a given status.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackendCount {
+    pub cluster: ClusterName,
+    pub status: BackendStatus,
+    pub count: i64,
+}
+
+/// A per-cluster count of some kind of object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterCount {
+    pub cluster: ClusterName,
+    pub count: i64,
+}
+
+/// Point-in-time counts used to render the controller's metrics endpoint.
+#[derive(Debug, Default)]
+pub struct ClusterMetrics {
+    /// Number of non-terminated backends, by cluster and status.
+    pub backends: Vec<BackendCount>,
+
+    /// Number of drones connected to a controller, by cluster.
+    pub drones: Vec<ClusterCount>,
+
+    /// Number of unexpired ACME TXT records, by cluster.
+    pub txt_records: Vec<ClusterCount>,
+}
+
+impl ClusterMetrics {
+    /// Groups the metrics by cluster. Clusters are ordered by name.
+    pub fn summaries(&self) -> BTreeMap<String, ClusterSummary> {
+        let mut summaries: BTreeMap<String, ClusterSummary> = BTreeMap::new();
+
+        for backends in &self.backends {
+            summaries
+                .entry(backends.cluster.to_string())
+                .or_default()
+                .backends_by_status
+                .insert(backends.status.to_string(), backends.count);
+        }
+
+        for drones in &self.drones {
+            summaries
+                .entry(drones.cluster.to_string())
+                .or_default()
+                .drones = drones.count;
+        }
+
+        for txt_records in &self.txt_records {
+            summaries
+                .entry(txt_records.cluster.to_string())
+                .or_default()
+                .txt_record_count = txt_records.count;
+        }
+
+        summaries
+    }
+}
+
+/// An overview of a single cluster.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ClusterSummary {
+    pub drones: i64,
+
+    /// Number of non-terminated backends, by status.
+    pub backends_by_status: BTreeMap<String, i64>,
+
+    pub txt_record_count: i64,
+}
+
+fn decode_cluster(cluster: String) -> sqlx::Result<ClusterName> {
+    ClusterName::try_from(cluster)
+        .map_err(|_| sqlx::Error::Decode("Failed to decode cluster name.".into()))
+}
 
 pub struct ClusterDatabase<'a> {
     pool: &'a PgPool,
@@ -25,6 +102,7 @@ impl<'a> ClusterDatabase<'a> {
                 node.controller as "controller!",
                 drone.ready as "ready?",
                 drone.draining as "draining?",
+                drone.max_backends,
                 drone.last_heartbeat as "last_drone_heartbeat",
                 controller.last_heartbeat as "last_controller_heartbeat!",
                 now() as "as_of!",
@@ -75,6 +153,7 @@ impl<'a> ClusterDatabase<'a> {
                         backend_count: node.backend_count.ok_or_else(|| {
                             sqlx::Error::Decode("Drone should have backend_count column.".into())
                         })? as u32,
+                        max_backends: node.max_backends.map(|m| m as u32),
                         last_heartbeat_age: node.as_of
                             - node.last_drone_heartbeat.ok_or_else(|| {
                                 sqlx::Error::Decode(
@@ -98,4 +177,134 @@ impl<'a> ClusterDatabase<'a> {
 
         Ok(ClusterState { proxies, drones })
     }
+
+    /// Returns backend, drone, and TXT record counts across all clusters.
+    /// Terminated backends are excluded, since their count grows without bound
+    /// until they are cleaned up, as are TXT records that are no longer served.
+    pub async fn metrics(&self) -> sqlx::Result<ClusterMetrics> {
+        let backends = sqlx::query!(
+            r#"
+            select
+                cluster,
+                last_status,
+                count(1) as "count!"
+            from backend
+            where last_status != $1
+            group by cluster, last_status
+            order by cluster, last_status
+            "#,
+            BackendStatus::Terminated.to_string(),
+        )
+        .fetch_all(self.pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(BackendCount {
+                cluster: decode_cluster(row.cluster)?,
+                status: BackendStatus::try_from(row.last_status)
+                    .map_err(|_| sqlx::Error::Decode("Failed to decode backend status.".into()))?,
+                count: row.count,
+            })
+        })
+        .collect::<sqlx::Result<_>>()?;
+
+        let drones = sqlx::query!(
+            r#"
+            select
+                node.cluster as "cluster!",
+                count(1) as "count!"
+            from node
+            inner join drone on node.id = drone.id
+            where node.controller is not null
+            and node.cluster is not null
+            group by node.cluster
+            order by node.cluster
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(ClusterCount {
+                cluster: decode_cluster(row.cluster)?,
+                count: row.count,
+            })
+        })
+        .collect::<sqlx::Result<_>>()?;
+
+        let txt_records = sqlx::query!(
+            r#"
+            select
+                cluster,
+                count(1) as "count!"
+            from acme_txt_entries
+            where txt_value is not null
+            and leased_at + $1 > now()
+            group by cluster
+            order by cluster
+            "#,
+            PgInterval::try_from(TXT_RECORD_EXPIRY).expect("valid interval"),
+        )
+        .fetch_all(self.pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(ClusterCount {
+                cluster: decode_cluster(row.cluster)?,
+                count: row.count,
+            })
+        })
+        .collect::<sqlx::Result<_>>()?;
+
+        Ok(ClusterMetrics {
+            backends,
+            drones,
+            txt_records,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_group_by_cluster() {
+        let backends = |cluster: &str, status, count| BackendCount {
+            cluster: cluster.parse().unwrap(),
+            status,
+            count,
+        };
+        let count = |cluster: &str, count| ClusterCount {
+            cluster: cluster.parse().unwrap(),
+            count,
+        };
+        let metrics = ClusterMetrics {
+            backends: vec![
+                backends("a.test", BackendStatus::Ready, 3),
+                backends("a.test", BackendStatus::Starting, 1),
+                backends("c.test", BackendStatus::Ready, 2),
+            ],
+            drones: vec![count("a.test", 2), count("b.test", 1)],
+            txt_records: vec![count("c.test", 1)],
+        };
+
+        let summaries = metrics.summaries();
+        let clusters: Vec<&String> = summaries.keys().collect();
+        assert_eq!(clusters, vec!["a.test", "b.test", "c.test"]);
+
+        let a = &summaries["a.test"];
+        assert_eq!(a.drones, 2);
+        assert_eq!(a.backends_by_status.get("ready"), Some(&3));
+        assert_eq!(a.backends_by_status.get("starting"), Some(&1));
+        assert_eq!(a.txt_record_count, 0);
+
+        let b = &summaries["b.test"];
+        assert_eq!(b.drones, 1);
+        assert!(b.backends_by_status.is_empty());
+
+        let c = &summaries["c.test"];
+        assert_eq!(c.drones, 0);
+        assert_eq!(c.txt_record_count, 1);
+    }
 }
@@ -2,7 +2,7 @@ use super::{
     backend::emit_state_change,
     backend_actions::create_pending_action,
     backend_key::{KEY_LEASE_RENEW_AFTER, KEY_LEASE_SOFT_TERMINATE_AFTER},
-    drone::DroneForSpawn,
+    drone::{DroneForSpawn, SpawnCandidate},
 };
 use crate::{
     client::PlaneClient,
@@ -11,21 +11,35 @@ use crate::{
         drone::DroneDatabase,
     },
     log_types::LoggableTime,
-    names::{BackendName, OrRandom},
+    names::{BackendName, DroneName, Name, OrRandom},
     protocol::{AcquiredKey, BackendAction, KeyDeadlines},
     types::{
-        BackendState, BackendStatus, BearerToken, ClusterName, ConnectRequest, ConnectResponse,
-        KeyConfig, RevokeRequest, SecretToken, SpawnConfig,
+        BackendState, BackendStatus, BearerToken, ClusterName, ClusterScheduleCandidates,
+        ConnectRequest, ConnectResponse, DroneExclusionReason, DronePoolName, KeyConfig,
+        RevokeRequest, ScheduleCandidate, ScheduleDryRunResponse, SecretToken, SpawnConfig,
     },
     util::random_token,
 };
 use serde_json::{Map, Value};
 use sqlx::{postgres::types::PgInterval, PgPool};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use valuable::Valuable;
 
 const TOKEN_LIFETIME_SECONDS: u64 = 3600;
 
+/// Upper bound on SpawnConfig::max_wait_seconds.
+pub const MAX_SPAWN_WAIT_SECONDS: i32 = 60;
+
+/// How often a connect request waiting for a drone checks for one.
+const DRONE_WAIT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Links a rescheduled backend to the lost backend it replaces.
+#[derive(Debug, Clone)]
+pub struct RetryLineage {
+    pub retry_of: BackendName,
+    pub retry_count: i32,
+}
+
 /// Unique violation error code in Postgres.
 /// NOTE: typically we should use "on conflict do nothing", but that only
 /// works with insert queries, not update queries.
@@ -42,11 +56,39 @@ fn violates_uniqueness(err: &sqlx::Error) -> bool {
 
 type Result<T> = std::result::Result<T, ConnectError>;
 
+/// Why a drone pinned by a spawn request can't accept the backend.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinnedDroneError {
+    #[error("no such drone in this cluster")]
+    Unknown,
+
+    #[error("drone is not in the requested pool")]
+    WrongPool,
+
+    #[error("drone is draining")]
+    Draining,
+
+    #[error("drone is not ready or its heartbeat is stale")]
+    Unhealthy,
+
+    #[error("drone has reached its backend limit")]
+    AtCapacity,
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ConnectError {
     #[error("No active drone available.")]
     NoDroneAvailable,
 
+    #[error("The selected drone reached its backend limit.")]
+    DroneAtCapacity,
+
+    #[error("Requested drone {drone} is unavailable: {reason}.")]
+    PinnedDroneUnavailable {
+        drone: DroneName,
+        reason: PinnedDroneError,
+    },
+
     #[error("Key held and tag does not match. {request_tag:?} != {key_tag:?}")]
     KeyHeld {
         request_tag: String,
@@ -83,7 +125,9 @@ impl ConnectError {
     fn retryable(&self) -> bool {
         matches!(
             self,
-            ConnectError::FailedToRemoveKey | ConnectError::FailedToAcquireKey
+            ConnectError::FailedToRemoveKey
+                | ConnectError::FailedToAcquireKey
+                | ConnectError::DroneAtCapacity
         )
     }
 }
@@ -91,6 +135,7 @@ impl ConnectError {
 /// Attempts to create a new backend that owns the given key. If the key is already held, returns
 /// Err(ConnectError::FailedToAcquireKey). If the key is not held, creates a new backend and
 /// returns Ok(backend_id).
+#[allow(clippy::too_many_arguments)]
 async fn create_backend_with_key(
     pool: &PgPool,
     key: &KeyConfig,
@@ -98,10 +143,62 @@ async fn create_backend_with_key(
     cluster: &ClusterName,
     drone_for_spawn: &DroneForSpawn,
     static_token: Option<&BearerToken>,
+    request: &ConnectRequest,
+    lineage: Option<&RetryLineage>,
 ) -> Result<BackendName> {
     let backend_id = spawn_config.id.clone().or_random();
+
+    // Keep what we need to spawn a replacement if the drone is lost. The key is
+    // included so that the replacement takes over the same key, and the cluster is
+    // pinned to the one we resolved so that it is not re-resolved from defaults.
+    let reschedule_request = spawn_config.reschedule.then(|| {
+        serde_json::to_value(ConnectRequest {
+            key: Some(key.clone()),
+            spawn_config: Some(SpawnConfig {
+                cluster: Some(cluster.clone()),
+                ..spawn_config.clone()
+            }),
+            ..request.clone()
+        })
+        .expect("ConnectRequest is always serializable")
+    });
     let mut txn = pool.begin().await?;
 
+    // Lock the drone so that concurrent spawns onto it are serialized, then check its
+    // backend limit. The count is a separate statement so that it sees backends
+    // committed by spawns we waited on for the lock.
+    let drone = sqlx::query!(
+        r#"
+        select max_backends
+        from drone
+        where id = $1
+        for update
+        "#,
+        drone_for_spawn.id.as_i32(),
+    )
+    .fetch_one(&mut *txn)
+    .await?;
+
+    if let Some(max_backends) = drone.max_backends {
+        let active_backends = sqlx::query!(
+            r#"
+            select count(*) as "count!"
+            from backend
+            where drone_id = $1
+            and last_status != $2
+            "#,
+            drone_for_spawn.id.as_i32(),
+            BackendStatus::Terminated.to_string(),
+        )
+        .fetch_one(&mut *txn)
+        .await?
+        .count;
+
+        if active_backends >= max_backends as i64 {
+            return Err(ConnectError::DroneAtCapacity);
+        }
+    }
+
     let initial_status = BackendStatus::Scheduled;
     let initial_state = BackendState::Scheduled;
 
@@ -120,9 +217,12 @@ async fn create_backend_with_key(
                 last_keepalive,
                 state,
                 static_token,
-                subdomain
+                subdomain,
+                reschedule_request,
+                retry_of,
+                retry_count
             )
-            values ($1, $2, $3, now(), $14, $4, now() + $5, $6, now(), $11, $12, $13)
+            values ($1, $2, $3, now(), $14, $4, now() + $5, $6, now(), $11, $12, $13, $15, $16, $17)
             returning id
         )
         insert into backend_key (id, key_name, namespace, tag, expires_at, fencing_token)
@@ -148,6 +248,9 @@ async fn create_backend_with_key(
         static_token.map(|t| t.to_string()),
         spawn_config.subdomain.as_ref().map(|s| s.to_string()),
         initial_status.as_int(),
+        reschedule_request,
+        lineage.map(|lineage| lineage.retry_of.to_string()),
+        lineage.map(|lineage| lineage.retry_count).unwrap_or(0),
     )
     .fetch_one(&mut *txn)
     .await;
@@ -223,6 +326,134 @@ async fn create_token(
     Ok((BearerToken::from(token), SecretToken::from(secret_token)))
 }
 
+/// Orders a cluster's drones for a spawn into the given pool the way pick_drone_for_spawn
+/// does: eligible drones from least to most loaded, followed by excluded drones along with
+/// the reason. The sort is stable, so equally loaded drones keep the order they were passed
+/// in, where pick_drone_for_spawn breaks ties at random.
+fn rank_candidates(
+    candidates: Vec<SpawnCandidate>,
+    pool: &DronePoolName,
+) -> Vec<(SpawnCandidate, Option<DroneExclusionReason>)> {
+    let mut ranked: Vec<_> = candidates
+        .into_iter()
+        .map(|candidate| {
+            let reason = candidate.exclusion_reason(pool);
+            (candidate, reason)
+        })
+        .collect();
+    ranked.sort_by_key(|(candidate, reason)| (reason.is_some(), candidate.backend_count));
+    ranked
+}
+
+/// Picks a drone from the given cluster, or else from the first of the spawn config's
+/// fallback clusters that has one available.
+async fn pick_drone_with_fallback(
+    pool: &PgPool,
+    cluster: &ClusterName,
+    spawn_config: &SpawnConfig,
+) -> Result<Option<(ClusterName, DroneForSpawn)>> {
+    for cluster in std::iter::once(cluster).chain(&spawn_config.fallback_clusters) {
+        if let Some(drone) = DroneDatabase::new(pool)
+            .pick_drone_for_spawn(cluster, &spawn_config.pool)
+            .await?
+        {
+            return Ok(Some((cluster.clone(), drone)));
+        }
+
+        tracing::info!(%cluster, "No drone available in cluster.");
+    }
+
+    Ok(None)
+}
+
+/// Checks that a drone pinned by a spawn request can accept the backend.
+async fn check_pinned_drone(
+    pool: &PgPool,
+    cluster: &ClusterName,
+    drone: &DroneName,
+    drone_pool: &DronePoolName,
+) -> Result<DroneForSpawn> {
+    let unavailable = |reason| ConnectError::PinnedDroneUnavailable {
+        drone: drone.clone(),
+        reason,
+    };
+
+    let candidate = DroneDatabase::new(pool)
+        .spawn_candidates(cluster, Some(drone))
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| unavailable(PinnedDroneError::Unknown))?;
+
+    match candidate.exclusion_reason(drone_pool) {
+        None => candidate
+            .into_drone_for_spawn()
+            .ok_or_else(|| unavailable(PinnedDroneError::Unhealthy)),
+        Some(DroneExclusionReason::WrongPool) => Err(unavailable(PinnedDroneError::WrongPool)),
+        Some(DroneExclusionReason::Draining) => Err(unavailable(PinnedDroneError::Draining)),
+        Some(DroneExclusionReason::Unhealthy) => Err(unavailable(PinnedDroneError::Unhealthy)),
+        Some(DroneExclusionReason::AtCapacity) => Err(unavailable(PinnedDroneError::AtCapacity)),
+    }
+}
+
+/// Reports where a backend with the given spawn config would be placed, and why,
+/// without creating it.
+pub async fn schedule_dry_run(
+    pool: &PgPool,
+    default_cluster: Option<&ClusterName>,
+    spawn_config: &SpawnConfig,
+) -> Result<ScheduleDryRunResponse> {
+    let cluster = spawn_config
+        .cluster
+        .as_ref()
+        .or(default_cluster)
+        .ok_or(ConnectError::NoClusterProvided)?;
+
+    // Spawns pinned to a drone do not fall back to other clusters.
+    let fallback_clusters = if spawn_config.drone.is_some() {
+        &[]
+    } else {
+        spawn_config.fallback_clusters.as_slice()
+    };
+
+    let mut placement = None;
+    let mut clusters = Vec::new();
+    for cluster in std::iter::once(cluster).chain(fallback_clusters) {
+        let mut candidates = DroneDatabase::new(pool)
+            .spawn_candidates(cluster, spawn_config.drone.as_ref())
+            .await?;
+        // The scheduler shuffles candidates to break ties at random. Sort them instead,
+        // so that dry runs are repeatable.
+        candidates.sort_by(|a, b| a.name.as_str().cmp(b.name.as_str()));
+
+        let ranked = rank_candidates(candidates, &spawn_config.pool);
+        if let Some((candidate, None)) = ranked.first() {
+            placement = Some((cluster.clone(), candidate.name.clone()));
+        }
+
+        clusters.push(ClusterScheduleCandidates {
+            cluster: cluster.clone(),
+            candidates: ranked
+                .into_iter()
+                .map(|(candidate, excluded)| ScheduleCandidate {
+                    drone: candidate.name,
+                    backend_count: candidate.backend_count as u32,
+                    excluded,
+                })
+                .collect(),
+        });
+
+        if placement.is_some() {
+            break;
+        }
+    }
+
+    Ok(ScheduleDryRunResponse {
+        placement,
+        clusters,
+    })
+}
+
 pub async fn revoke(pool: &PgPool, request: &RevokeRequest) -> Result<()> {
     sqlx::query!(
         r#"
@@ -242,6 +473,7 @@ async fn attempt_connect(
     default_cluster: Option<&ClusterName>,
     request: &ConnectRequest,
     client: &PlaneClient,
+    lineage: Option<&RetryLineage>,
 ) -> Result<ConnectResponse> {
     let key = if let Some(key) = &request.key {
         // Request includes a key, so we need to check if it is held.
@@ -315,10 +547,15 @@ async fn attempt_connect(
         .or(default_cluster)
         .ok_or(ConnectError::NoClusterProvided)?;
 
-    let drone = DroneDatabase::new(pool)
-        .pick_drone_for_spawn(cluster, &spawn_config.pool)
-        .await?
-        .ok_or(ConnectError::NoDroneAvailable)?;
+    let (cluster, drone) = if let Some(drone) = &spawn_config.drone {
+        let drone = check_pinned_drone(pool, cluster, drone, &spawn_config.pool).await?;
+        (cluster.clone(), drone)
+    } else {
+        pick_drone_with_fallback(pool, cluster, spawn_config)
+            .await?
+            .ok_or(ConnectError::NoDroneAvailable)?
+    };
+    let cluster = &cluster;
 
     // If the spawn config specifies a static token, create one and use it.
     // Note that if this is non-None, the call to create_token below will be skipped.
@@ -333,6 +570,8 @@ async fn attempt_connect(
         cluster,
         &drone,
         bearer_token.as_ref(),
+        request,
+        lineage,
     )
     .await?;
     tracing::info!(backend_id = backend_id.as_value(), "Created backend");
@@ -372,10 +611,46 @@ pub async fn connect(
     request: &ConnectRequest,
     client: &PlaneClient,
 ) -> Result<ConnectResponse> {
+    connect_with_lineage(pool, default_cluster, request, client, None).await
+}
+
+/// Spawns a replacement for a backend that was lost before it became ready, by
+/// re-running the connect request that created it.
+pub async fn reschedule(
+    pool: &PgPool,
+    default_cluster: Option<&ClusterName>,
+    request: &ConnectRequest,
+    client: &PlaneClient,
+    lineage: &RetryLineage,
+) -> Result<ConnectResponse> {
+    connect_with_lineage(pool, default_cluster, request, client, Some(lineage)).await
+}
+
+async fn connect_with_lineage(
+    pool: &PgPool,
+    default_cluster: Option<&ClusterName>,
+    request: &ConnectRequest,
+    client: &PlaneClient,
+    lineage: Option<&RetryLineage>,
+) -> Result<ConnectResponse> {
+    let max_wait = request
+        .spawn_config
+        .as_ref()
+        .and_then(|spawn_config| spawn_config.max_wait_seconds)
+        .map(|seconds| seconds.clamp(0, MAX_SPAWN_WAIT_SECONDS))
+        .unwrap_or(0);
+    let wait_deadline = Instant::now() + Duration::from_secs(max_wait as u64);
+
     let mut attempt = 1;
     loop {
-        match attempt_connect(pool, default_cluster, request, client).await {
+        match attempt_connect(pool, default_cluster, request, client, lineage).await {
             Ok(response) => return Ok(response),
+            Err(ConnectError::NoDroneAvailable) if Instant::now() < wait_deadline => {
+                // Drones may be joining the cluster (e.g. from an autoscaler), so wait
+                // for one instead of failing.
+                tracing::info!("No drone available, waiting for one.");
+                tokio::time::sleep(DRONE_WAIT_INTERVAL).await;
+            }
             Err(error) => {
                 if !error.retryable() || attempt >= 3 {
                     return Err(error);
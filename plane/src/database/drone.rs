@@ -1,13 +1,58 @@
+use super::subscribe::{emit, NotificationPayload};
 use crate::{
     heartbeat_consts::UNHEALTHY_SECONDS,
     names::{ControllerName, DroneName},
-    types::{BackendStatus, ClusterName, DronePoolName, NodeId},
+    types::{BackendStatus, ClusterName, DroneExclusionReason, DronePoolName, NodeId},
 };
 use chrono::{DateTime, Utc};
-use sqlx::{postgres::types::PgInterval, query, PgPool};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::types::PgInterval, query, PgConnection, PgPool};
 use std::str::FromStr;
 use std::time::Duration;
 
+/// Emitted when a draining drone has no remaining non-terminated backends, i.e. it
+/// can be shut down without interrupting anything.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DroneDrainedNotification {
+    pub drone_id: NodeId,
+}
+
+impl NotificationPayload for DroneDrainedNotification {
+    fn kind() -> &'static str {
+        "drone_drained"
+    }
+}
+
+/// Emits a DroneDrainedNotification if the given drone is draining and all of its
+/// backends are terminated.
+pub(super) async fn emit_if_drained(db: &mut PgConnection, drone_id: NodeId) -> sqlx::Result<()> {
+    let result = query!(
+        r#"
+        select id
+        from drone
+        where id = $1
+        and draining = true
+        and not exists (
+            select 1
+            from backend
+            where drone_id = $1
+            and last_status != $2
+        )
+        "#,
+        drone_id.as_i32(),
+        BackendStatus::Terminated.to_string(),
+    )
+    .fetch_optional(&mut *db)
+    .await?;
+
+    if result.is_some() {
+        emit(db, &DroneDrainedNotification { drone_id }).await?;
+    }
+
+    Ok(())
+}
+
 pub struct DroneDatabase<'a> {
     pool: &'a PgPool,
 }
@@ -22,17 +67,20 @@ impl<'a> DroneDatabase<'a> {
         id: NodeId,
         ready: bool,
         pool: DronePoolName,
+        max_backends: Option<u32>,
     ) -> sqlx::Result<()> {
         query!(
             r#"
-            insert into drone (id, draining, ready, pool)
-            values ($1, false, $2, $3)
+            insert into drone (id, draining, ready, pool, max_backends)
+            values ($1, false, $2, $3, $4)
             on conflict (id) do update set
-                ready = $2
+                ready = $2,
+                max_backends = $4
             "#,
             id.as_i32(),
             ready,
             pool.to_string(),
+            max_backends.map(|m| m as i32),
         )
         .execute(self.pool)
         .await?;
@@ -57,11 +105,42 @@ impl<'a> DroneDatabase<'a> {
         .fetch_optional(self.pool)
         .await?;
 
-        if let Some(was_draining) = result {
-            Ok(!was_draining.was_draining)
-        } else {
-            Err(sqlx::Error::RowNotFound)
+        let Some(was_draining) = result else {
+            return Err(sqlx::Error::RowNotFound);
+        };
+
+        if !was_draining.was_draining {
+            let mut conn = self.pool.acquire().await?;
+            emit_if_drained(&mut conn, id).await?;
         }
+
+        Ok(!was_draining.was_draining)
+    }
+
+    /// Clears the draining flag of a drone, making it eligible for scheduling again.
+    /// Returns true if the drone was draining before the call.
+    pub async fn undrain(&self, id: NodeId) -> sqlx::Result<bool> {
+        let result = query!(
+            r#"
+            update drone
+            set draining = false
+            where id = $1
+            returning (
+                select draining
+                from drone
+                where id = $1
+            ) as "was_draining!"
+            "#,
+            id.as_i32(),
+        )
+        .fetch_optional(self.pool)
+        .await?;
+
+        let Some(was_draining) = result else {
+            return Err(sqlx::Error::RowNotFound);
+        };
+
+        Ok(was_draining.was_draining)
     }
 
     pub async fn heartbeat(&self, id: NodeId, local_time: DateTime<Utc>) -> sqlx::Result<()> {
@@ -171,67 +250,142 @@ impl<'a> DroneDatabase<'a> {
         Ok(drones)
     }
 
-    /// TODO: simple algorithm until we collect more metrics.
+    /// Picks the least loaded drone of the cluster that can accept a backend for the
+    /// given pool, breaking ties at random.
     pub async fn pick_drone_for_spawn(
         &self,
         cluster: &ClusterName,
         pool: &DronePoolName,
     ) -> sqlx::Result<Option<DroneForSpawn>> {
+        let mut candidates = self.spawn_candidates(cluster, None).await?;
+        candidates.shuffle(&mut rand::thread_rng());
+
+        let candidate = candidates
+            .into_iter()
+            .filter(|candidate| candidate.exclusion_reason(pool).is_none())
+            .min_by_key(|candidate| candidate.backend_count);
+
+        Ok(candidate.and_then(SpawnCandidate::into_drone_for_spawn))
+    }
+
+    /// Returns the drones of a cluster (or, if `drone` is given, only that drone) along
+    /// with everything needed to decide whether a backend can be placed on them.
+    /// Ineligible drones are included, so that callers can report why they were skipped.
+    pub async fn spawn_candidates(
+        &self,
+        cluster: &ClusterName,
+        drone: Option<&DroneName>,
+    ) -> sqlx::Result<Vec<SpawnCandidate>> {
         let result = query!(
             r#"
             select
                 drone.id,
                 node.name,
-                drone.last_local_time as "last_local_time!"
+                drone.pool,
+                drone.ready,
+                drone.draining,
+                drone.max_backends,
+                drone.last_local_time,
+                coalesce(
+                    now() - drone.last_heartbeat < $3
+                    and now() - controller.last_heartbeat < $3
+                    and controller.is_online,
+                    false
+                ) as "healthy!",
+                (
+                    select
+                        count(*)
+                    from backend
+                    where drone_id = node.id
+                    and last_status != $4
+                ) as "backend_count!"
             from node
-            left join drone
+            inner join drone
                 on node.id = drone.id
             left join controller
                 on node.controller = controller.id
             where
-                drone.ready = true
-                and controller is not null
-                and cluster = $1
-                and now() - drone.last_heartbeat < $2
-                and now() - controller.last_heartbeat < $2
-                and controller.is_online = true
-                and draining = false
-                and last_local_time is not null
-                and pool = $3
-            order by (
-                select
-                    count(*)
-                from backend
-                where drone_id = node.id
-                and last_status != $4
-            ) asc, random()
-            limit 1
+                node.cluster = $1
+                and ($2::text is null or node.name = $2)
             "#,
             cluster.to_string(),
+            drone.map(|drone| drone.to_string()),
             PgInterval::try_from(Duration::from_secs(UNHEALTHY_SECONDS as _))
                 .expect("valid interval"),
-            pool.to_string(),
             BackendStatus::Terminated.to_string(),
         )
-        .fetch_optional(self.pool)
+        .fetch_all(self.pool)
         .await?;
 
-        let result = match result {
-            Some(result) => {
-                let id = NodeId::from(result.id);
-                let drone = DroneName::try_from(result.name).expect("valid drone name");
-                let last_local_time = result.last_local_time;
-
-                Some(DroneForSpawn {
-                    id,
-                    drone,
-                    last_local_time,
-                })
+        let candidates = result
+            .into_iter()
+            .map(|r| SpawnCandidate {
+                id: NodeId::from(r.id),
+                name: DroneName::try_from(r.name).expect("valid drone name"),
+                pool: r.pool.into(),
+                ready: r.ready,
+                draining: r.draining,
+                healthy: r.healthy,
+                last_local_time: r.last_local_time,
+                backend_count: r.backend_count,
+                max_backends: r.max_backends,
+            })
+            .collect();
+
+        Ok(candidates)
+    }
+}
+
+/// A drone considered for a spawn, with the fields needed to check whether it can
+/// accept the backend.
+#[derive(Debug, Clone)]
+pub struct SpawnCandidate {
+    pub id: NodeId,
+    pub name: DroneName,
+    pub pool: DronePoolName,
+    pub ready: bool,
+    pub draining: bool,
+    /// Whether the drone and its controller have heartbeated recently.
+    pub healthy: bool,
+    pub last_local_time: Option<DateTime<Utc>>,
+    /// Number of non-terminated backends on the drone.
+    pub backend_count: i64,
+    pub max_backends: Option<i32>,
+}
+
+impl SpawnCandidate {
+    /// Returns the reason this drone can't accept a backend for the given pool, or
+    /// None if it can.
+    pub fn exclusion_reason(&self, pool: &DronePoolName) -> Option<DroneExclusionReason> {
+        if self.pool != *pool {
+            return Some(DroneExclusionReason::WrongPool);
+        }
+
+        if self.draining {
+            return Some(DroneExclusionReason::Draining);
+        }
+
+        if !self.ready || !self.healthy || self.last_local_time.is_none() {
+            return Some(DroneExclusionReason::Unhealthy);
+        }
+
+        if let Some(max_backends) = self.max_backends {
+            if self.backend_count >= max_backends as i64 {
+                return Some(DroneExclusionReason::AtCapacity);
             }
-            None => return Ok(None),
-        };
+        }
+
+        None
+    }
 
-        Ok(result)
+    /// Converts the candidate to a DroneForSpawn. Returns None if the drone has not
+    /// reported its local time, which an eligible drone always has.
+    pub fn into_drone_for_spawn(self) -> Option<DroneForSpawn> {
+        Some(DroneForSpawn {
+            id: self.id,
+            drone: self.name,
+            last_local_time: self.last_local_time?,
+        })
     }
 }
 
@@ -4,7 +4,7 @@ use self::{
     backend_actions::BackendActionDatabase,
     backend_key::KeysDatabase,
     cluster::ClusterDatabase,
-    connect::ConnectError,
+    connect::{ConnectError, RetryLineage},
     controller::ControllerDatabase,
     drone::DroneDatabase,
     node::NodeDatabase,
@@ -12,11 +12,18 @@ use self::{
 };
 use crate::{
     client::PlaneClient,
-    types::{ClusterName, ConnectRequest, ConnectResponse, RevokeRequest},
+    controller::webhook::WebhookNotifier,
+    types::{
+        ClusterName, ConnectRequest, ConnectResponse, RevokeRequest, ScheduleDryRunResponse,
+        SpawnConfig,
+    },
 };
 use serde_json::Value;
 use sqlx::{postgres::PgPoolOptions, PgPool};
-use std::sync::{Arc, OnceLock};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, OnceLock},
+};
 use tokio::sync::broadcast::Receiver;
 
 pub mod acme;
@@ -46,6 +53,7 @@ pub async fn connect(db: &str) -> sqlx::Result<PlaneDatabase> {
 pub struct PlaneDatabase {
     pub pool: PgPool,
     subscription_manager: Arc<OnceLock<EventSubscriptionManager>>,
+    webhook: Option<Arc<WebhookNotifier>>,
 }
 
 impl PlaneDatabase {
@@ -53,6 +61,16 @@ impl PlaneDatabase {
         Self {
             pool,
             subscription_manager: Arc::default(),
+            webhook: None,
+        }
+    }
+
+    /// Returns a handle to the same database that posts each backend state change it
+    /// applies to the given webhook.
+    pub fn with_webhook(self, webhook: Arc<WebhookNotifier>) -> Self {
+        Self {
+            webhook: Some(webhook),
+            ..self
         }
     }
 
@@ -103,6 +121,25 @@ impl PlaneDatabase {
     ) -> Result<ConnectResponse, ConnectError> {
         connect::connect(&self.pool, default_cluster, request, client).await
     }
+
+    pub async fn reschedule(
+        &self,
+        default_cluster: Option<&ClusterName>,
+        request: &ConnectRequest,
+        client: &PlaneClient,
+        lineage: &RetryLineage,
+    ) -> Result<ConnectResponse, ConnectError> {
+        connect::reschedule(&self.pool, default_cluster, request, client, lineage).await
+    }
+
+    pub async fn schedule_dry_run(
+        &self,
+        default_cluster: Option<&ClusterName>,
+        spawn_config: &SpawnConfig,
+    ) -> Result<ScheduleDryRunResponse, ConnectError> {
+        connect::schedule_dry_run(&self.pool, default_cluster, spawn_config).await
+    }
+
     pub async fn revoke(&self, request: &RevokeRequest) -> Result<(), ConnectError> {
         connect::revoke(&self.pool, request).await
     }
@@ -136,4 +173,13 @@ impl PlaneDatabase {
     pub fn subscribe_all_events(&self) -> Receiver<Notification<Value>> {
         self.subscription_manager().subscribe_all_events()
     }
+
+    /// Returns the number of notifications received by this process whose payload could
+    /// not be deserialized, by kind.
+    pub fn malformed_notifications(&self) -> BTreeMap<String, u64> {
+        self.subscription_manager
+            .get()
+            .map(EventSubscriptionManager::malformed_notifications)
+            .unwrap_or_default()
+    }
 }
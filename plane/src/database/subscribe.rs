@@ -6,9 +6,9 @@ use serde_json::Value;
 use sqlx::{postgres::PgListener, PgConnection, PgPool};
 use std::{
     any::Any,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::Debug,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 use tokio::{
     sync::broadcast::{Receiver, Sender},
@@ -17,6 +17,9 @@ use tokio::{
 
 type ListenerMap = Arc<RwLock<HashMap<(String, Option<String>), Box<dyn TypedSender>>>>;
 
+/// Number of notifications whose payload could not be deserialized, by kind.
+type MalformedCounts = Arc<Mutex<BTreeMap<String, u64>>>;
+
 const EVENT_CHANNEL: &str = "plane_events";
 
 pub trait NotificationPayload:
@@ -26,7 +29,9 @@ pub trait NotificationPayload:
 }
 
 trait TypedSender: Send + Sync {
-    fn send(&self, value: Notification<Value>);
+    /// Deserializes the payload and sends the notification to subscribers. Returns an
+    /// error if the payload does not match the subscribers' type.
+    fn send(&self, value: Notification<Value>) -> Result<(), serde_json::Error>;
 
     fn receiver_count(&self) -> usize;
 
@@ -34,12 +39,21 @@ trait TypedSender: Send + Sync {
 }
 
 impl<T: NotificationPayload> TypedSender for Sender<Notification<T>> {
-    fn send(&self, value: Notification<Value>) {
-        let payload: T = match serde_json::from_value(value.payload) {
+    fn send(&self, value: Notification<Value>) -> Result<(), serde_json::Error> {
+        // Deserialize from a reference so that the raw payload is still available to log
+        // if it does not match the expected type (e.g. due to a version mismatch).
+        let payload = match T::deserialize(&value.payload) {
             Ok(payload) => payload,
             Err(err) => {
-                tracing::error!(?err, "Failed to deserialize notification payload.");
-                return;
+                tracing::error!(
+                    ?err,
+                    id = ?value.id,
+                    kind = %value.kind,
+                    key = ?value.key,
+                    payload = %value.payload,
+                    "Failed to deserialize notification payload."
+                );
+                return Err(err);
             }
         };
 
@@ -54,6 +68,8 @@ impl<T: NotificationPayload> TypedSender for Sender<Notification<T>> {
         if let Err(err) = Sender::send(self, value) {
             tracing::error!(?err, "Failed to send notification.");
         }
+
+        Ok(())
     }
 
     fn receiver_count(&self) -> usize {
@@ -110,6 +126,8 @@ pub struct EventSubscriptionManager {
 
     /// Maps from (kind, optional_key) to a sender.
     listeners: ListenerMap,
+
+    malformed: MalformedCounts,
 }
 
 impl Drop for EventSubscriptionManager {
@@ -137,14 +155,47 @@ pub struct Notification<T> {
     pub payload: T,
 }
 
+/// Sends a notification to the listeners for its kind, both those for its key (if it has
+/// one) and those for every key. If its payload does not match the listeners' type, it is
+/// counted in `malformed`.
+fn dispatch(
+    listeners: &ListenerMap,
+    malformed: &MalformedCounts,
+    notification: Notification<Value>,
+) {
+    let listeners = listeners.read().expect("Listener map is poisoned.");
+    let kind = notification.kind.clone();
+    let mut is_malformed = false;
+
+    if let Some(key) = notification.key.as_ref() {
+        if let Some(sender) = listeners.get(&(kind.clone(), Some(key.clone()))) {
+            is_malformed |= sender.send(notification.clone()).is_err();
+        }
+    }
+
+    if let Some(sender) = listeners.get(&(kind.clone(), None)) {
+        is_malformed |= sender.send(notification).is_err();
+    }
+
+    if is_malformed {
+        *malformed
+            .lock()
+            .expect("Malformed notification counts are poisoned.")
+            .entry(kind)
+            .or_default() += 1;
+    }
+}
+
 impl EventSubscriptionManager {
     pub fn new(db: &PgPool) -> Self {
         let listeners: ListenerMap = Arc::new(RwLock::new(HashMap::new()));
+        let malformed = MalformedCounts::default();
         let all_events = Sender::new(100);
 
         let handle = {
             let all_events = all_events.clone();
             let listeners = listeners.clone();
+            let malformed = malformed.clone();
             let db = db.clone();
 
             tokio::spawn(async move {
@@ -156,22 +207,7 @@ impl EventSubscriptionManager {
                         let _ = all_events.send(notification.clone());
                     }
 
-                    let listeners = listeners.read().expect("Listener map is poisoned.");
-
-                    // If the notification has a key, we send it both to the global listeners and
-                    // the listeners for the specific key.
-                    if let Some(key) = notification.key.as_ref() {
-                        if let Some(sender) =
-                            listeners.get(&(notification.kind.clone(), Some(key.clone())))
-                        {
-                            sender.send(notification.clone());
-                        }
-                    }
-
-                    // Send the notification to the global listeners.
-                    if let Some(sender) = listeners.get(&(notification.kind.clone(), None)) {
-                        sender.send(notification);
-                    }
+                    dispatch(&listeners, &malformed, notification);
                 };
 
                 'outer: loop {
@@ -230,7 +266,11 @@ impl EventSubscriptionManager {
                             match serde_json::from_str(notification.payload()) {
                                 Ok(notification) => notification,
                                 Err(err) => {
-                                    tracing::error!(?err, "Failed to deserialize notification.");
+                                    tracing::error!(
+                                        ?err,
+                                        payload = notification.payload(),
+                                        "Failed to deserialize notification."
+                                    );
                                     continue;
                                 }
                             };
@@ -260,9 +300,19 @@ impl EventSubscriptionManager {
             all_events,
             handle,
             listeners,
+            malformed,
         }
     }
 
+    /// Returns the number of notifications received whose payload could not be
+    /// deserialized as the type subscribed to, by kind.
+    pub fn malformed_notifications(&self) -> BTreeMap<String, u64> {
+        self.malformed
+            .lock()
+            .expect("Malformed notification counts are poisoned.")
+            .clone()
+    }
+
     pub async fn clean_up_events(db: &PgPool, min_age_days: i32) -> Result<(), sqlx::Error> {
         sqlx::query!(
             r#"
@@ -439,3 +489,51 @@ pub async fn emit_ephemeral_with_key<T: NotificationPayload>(
 ) -> Result<(), sqlx::Error> {
     emit_ephemeral_impl(db, Some(key), payload).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::drone::DroneDrainedNotification;
+
+    #[test]
+    fn malformed_payload_is_counted_by_kind() {
+        let listeners: ListenerMap = Arc::default();
+        let malformed = MalformedCounts::default();
+
+        let sender = Sender::<Notification<DroneDrainedNotification>>::new(10);
+        let mut receiver = sender.subscribe();
+        listeners.write().unwrap().insert(
+            (DroneDrainedNotification::kind().to_string(), None),
+            Box::new(sender),
+        );
+
+        let notification = |payload| Notification {
+            id: None,
+            timestamp: Utc::now(),
+            kind: DroneDrainedNotification::kind().to_string(),
+            key: None,
+            payload,
+        };
+
+        dispatch(
+            &listeners,
+            &malformed,
+            notification(serde_json::json!({ "drone_id": 1 })),
+        );
+        dispatch(
+            &listeners,
+            &malformed,
+            notification(serde_json::json!({ "unexpected": true })),
+        );
+
+        assert!(receiver.try_recv().is_ok());
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(
+            malformed
+                .lock()
+                .unwrap()
+                .get(DroneDrainedNotification::kind()),
+            Some(&1)
+        );
+    }
+}
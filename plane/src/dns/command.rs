@@ -1,3 +1,4 @@
+use super::LETS_ENCRYPT_CAA_ISSUER;
 use crate::names::{AcmeDnsServerName, OrRandom};
 use clap::Parser;
 use url::Url;
@@ -22,6 +23,16 @@ pub struct DnsOpts {
 
     #[clap(long, default_value = "53")]
     port: u16,
+
+    /// Certificate authority domain to return in CAA records for cluster names.
+    /// This should match the CA behind the proxies' --acme-endpoint. The default
+    /// matches the proxies' default of Let's Encrypt.
+    #[clap(long, default_value = LETS_ENCRYPT_CAA_ISSUER)]
+    caa_issuer: String,
+
+    /// Answer CAA queries with no records, which permits any CA to issue certificates.
+    #[clap(long)]
+    disable_caa: bool,
 }
 
 impl DnsOpts {
@@ -31,6 +42,7 @@ impl DnsOpts {
             controller_url: self.controller_url,
             port: self.port,
             zone: Some(self.zone),
+            caa_issuer: (!self.disable_caa).then_some(self.caa_issuer),
         }
     }
 }
@@ -8,10 +8,10 @@ use crate::{
     typed_socket::client::TypedSocketConnector,
     types::ClusterName,
 };
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use std::{net::Ipv4Addr, sync::Arc, time::Duration};
+use std::{net::Ipv4Addr, str::FromStr, sync::Arc, time::Duration};
 use tokio::{
     net::{TcpListener, UdpSocket},
     select,
@@ -22,7 +22,10 @@ use trust_dns_server::{
     authority::MessageResponseBuilder,
     proto::{
         op::{Header, ResponseCode},
-        rr::{rdata::TXT, RData, Record, RecordType},
+        rr::{
+            rdata::{CAA, TXT},
+            Name, RData, Record, RecordType,
+        },
     },
     server::{Request, RequestHandler, ResponseHandler, ResponseInfo},
     ServerFuture,
@@ -35,11 +38,16 @@ mod name_to_cluster;
 
 const TCP_TIMEOUT_SECONDS: u64 = 10;
 
+/// CAA issuer domain of Let's Encrypt, which the proxy uses by default (see
+/// LETS_ENCRYPT_PRODUCTION_DIRECTORY).
+pub const LETS_ENCRYPT_CAA_ISSUER: &str = "letsencrypt.org";
+
 struct AcmeDnsServer {
     loop_handle: Option<JoinHandle<()>>,
     send: Sender<MessageFromDns>,
     request_map: Arc<DashMap<ClusterName, Sender<Option<String>>>>,
     name_to_cluster: NameToCluster,
+    caa_issuer: Option<Name>,
 }
 
 impl AcmeDnsServer {
@@ -47,6 +55,7 @@ impl AcmeDnsServer {
         name: AcmeDnsServerName,
         mut client: TypedSocketConnector<MessageFromDns>,
         zone: Option<String>,
+        caa_issuer: Option<Name>,
     ) -> Self {
         let (send, mut recv) = broadcast::channel::<MessageFromDns>(1);
         let request_map: Arc<DashMap<ClusterName, Sender<Option<String>>>> = Arc::default();
@@ -101,6 +110,7 @@ impl AcmeDnsServer {
             send,
             request_map,
             name_to_cluster: NameToCluster::new(zone),
+            caa_issuer,
         }
     }
 
@@ -170,6 +180,30 @@ impl AcmeDnsServer {
 
                 Ok(result)
             }
+            RecordType::CAA => {
+                let cluster = self.name_to_cluster.caa_cluster_name(&name);
+
+                tracing::info!(?cluster, issuer=?self.caa_issuer, "CAA query.");
+
+                // If CAA is disabled or the name is not a cluster name, answer with no
+                // records (rather than an error), which tells the CA that any issuer is
+                // permitted.
+                let result: Vec<Record> = self
+                    .caa_issuer
+                    .as_ref()
+                    .filter(|_| cluster.is_some())
+                    .map(|issuer| {
+                        Record::from_rdata(
+                            request.query().name().into(),
+                            300,
+                            RData::CAA(CAA::new_issue(false, Some(issuer.clone()), vec![])),
+                        )
+                    })
+                    .into_iter()
+                    .collect();
+
+                Ok(result)
+            }
             _ => Err(error::DnsError {
                 code: ResponseCode::NotImp,
                 message: format!("Unsupported query type: {:?}", request.query().query_type()),
@@ -197,6 +231,9 @@ impl RequestHandler for AcmeDnsServer {
 
         let result = match self.do_lookup(request).await {
             Ok(answers) => {
+                // This server is authoritative for the names it answers, so an empty
+                // answer means that no such record exists.
+                header.set_authoritative(true);
                 let response = builder.build(header, answers.iter(), vec![], vec![], vec![]);
                 response_handle.send_response(response).await
             }
@@ -223,8 +260,14 @@ pub async fn run_dns_with_listener(
     client: PlaneClient,
     listener: TcpListener,
     zone: Option<String>,
+    caa_issuer: Option<Name>,
 ) -> std::result::Result<(), Box<dyn std::error::Error>> {
-    let mut fut = ServerFuture::new(AcmeDnsServer::new(name, client.dns_connection(), zone));
+    let mut fut = ServerFuture::new(AcmeDnsServer::new(
+        name,
+        client.dns_connection(),
+        zone,
+        caa_issuer,
+    ));
 
     let addr = listener.local_addr()?;
 
@@ -255,13 +298,27 @@ pub struct DnsConfig {
     pub controller_url: Url,
     pub port: u16,
     pub zone: Option<String>,
+
+    /// Issuer domain to return in answers to CAA queries. Defaults to
+    /// LETS_ENCRYPT_CAA_ISSUER. If null, CAA queries are answered with no records.
+    #[serde(default = "default_caa_issuer")]
+    pub caa_issuer: Option<String>,
+}
+
+fn default_caa_issuer() -> Option<String> {
+    Some(LETS_ENCRYPT_CAA_ISSUER.to_string())
 }
 
 pub async fn run_dns(config: DnsConfig) -> anyhow::Result<()> {
     let client = PlaneClient::new(config.controller_url);
     let ip_port_pair = (Ipv4Addr::UNSPECIFIED, config.port);
     let listener = TcpListener::bind(ip_port_pair).await?;
-    run_dns_with_listener(config.name, client, listener, config.zone)
+    let caa_issuer = config
+        .caa_issuer
+        .map(|issuer| Name::from_str(&issuer))
+        .transpose()
+        .context("Invalid CAA issuer")?;
+    run_dns_with_listener(config.name, client, listener, config.zone, caa_issuer)
         .await
         .map_err(|err| anyhow!("Error running DNS server {:?}", err))
 }
@@ -43,6 +43,15 @@ impl NameToCluster {
             }
         }
     }
+
+    /// Maps the name of a CAA query to a cluster name. CAs look up CAA records on the
+    /// name a certificate is requested for, i.e. <cluster name> or *.<cluster name>,
+    /// rather than on the _acme-challenge record, so this does not depend on the zone.
+    pub fn caa_cluster_name(&self, name: &str) -> Option<ClusterName> {
+        let name = name.strip_suffix('.').unwrap_or(name);
+        let name = name.strip_prefix("*.").unwrap_or(name);
+        ClusterName::from_str(name).ok()
+    }
 }
 
 #[cfg(test)]
@@ -91,4 +100,19 @@ mod test {
             Some(ClusterName::from_str("foo.bar.baz").unwrap())
         );
     }
+
+    #[test]
+    fn test_caa_cluster_name() {
+        let name_to_cluster = super::NameToCluster::new(Some("example.com".to_string()));
+
+        assert_eq!(
+            name_to_cluster.caa_cluster_name("foo.bar.baz."),
+            Some(ClusterName::from_str("foo.bar.baz").unwrap())
+        );
+
+        assert_eq!(
+            name_to_cluster.caa_cluster_name("*.foo.bar.baz"),
+            Some(ClusterName::from_str("foo.bar.baz").unwrap())
+        );
+    }
 }
@@ -156,7 +156,7 @@ impl BackendManager {
                     tracing::info!(%backend_id, "preparing...");
                     if let Err(err) = runtime.prepare(&executor_config).await {
                         tracing::error!(?err, %backend_id, "failed to prepare");
-                        state.to_terminated(None)
+                        state.to_failed(format!("Failed to prepare backend: {}", err))
                     } else {
                         tracing::info!(%backend_id, "done preparing...");
                         state.to_starting()
@@ -185,7 +185,7 @@ impl BackendManager {
                         Ok(spawn_result) => spawn_result,
                         Err(err) => {
                             tracing::error!(?err, "failed to spawn backend");
-                            return state.to_terminated(None);
+                            return state.to_failed(format!("Failed to spawn backend: {}", err));
                         }
                     };
 
@@ -280,7 +280,11 @@ impl BackendManager {
         Ok(())
     }
 
-    pub fn mark_terminated(self: &Arc<Self>, exit_code: Option<i32>) -> Result<()> {
+    pub fn mark_terminated(
+        self: &Arc<Self>,
+        exit_code: Option<i32>,
+        oom_killed: bool,
+    ) -> Result<()> {
         let state = self
             .state
             .lock()
@@ -290,9 +294,14 @@ impl BackendManager {
         tracing::info!(
             backend_id = self.backend_id.as_value(),
             state = state.as_value(),
+            oom_killed,
             "Marking backend as terminated"
         );
-        self.set_state(state.to_terminated(exit_code));
+        if oom_killed {
+            self.set_state(state.to_out_of_memory(exit_code));
+        } else {
+            self.set_state(state.to_terminated(exit_code));
+        }
 
         Ok(())
     }
@@ -45,6 +45,10 @@ pub struct DroneOpts {
     #[clap(long, default_value_t = DronePoolName::default())]
     pool: DronePoolName,
 
+    /// Optional maximum number of non-terminated backends to run on this drone.
+    #[clap(long)]
+    max_backends: Option<u32>,
+
     /// Optional base directory under which backends are allowed to mount directories.
     #[clap(long)]
     mount_base: Option<PathBuf>,
@@ -97,6 +101,7 @@ impl DroneOpts {
             ip,
             db_path: self.db,
             pool: self.pool,
+            max_backends: self.max_backends,
             auto_prune: None,      // deprecated
             cleanup_min_age: None, // deprecated
             docker_config: None,   // deprecated
@@ -48,7 +48,8 @@ impl Executor {
                             "Backend terminated.",
                         );
 
-                        if let Err(err) = manager.mark_terminated(event.exit_code) {
+                        if let Err(err) = manager.mark_terminated(event.exit_code, event.oom_killed)
+                        {
                             tracing::error!(?err, "Error marking backend as terminated.");
                         }
                     }
@@ -223,6 +224,7 @@ impl Executor {
                                     termination: None,
                                     reason: Some(TerminationReason::Lost),
                                     exit_code: None,
+                                    message: None,
                                 },
                                 Utc::now(),
                             )?;
@@ -231,7 +231,7 @@ impl Drone {
             }
         };
 
-        let connector = client.drone_connection(&config.cluster, &config.pool);
+        let connector = client.drone_connection(&config.cluster, &config.pool, config.max_backends);
 
         let sqlite_connection = if let Some(db_path) = config.db_path.as_ref() {
             if !db_path.exists() {
@@ -284,6 +284,11 @@ pub struct DroneConfig {
     pub ip: IpAddr,
     pub db_path: Option<PathBuf>,
 
+    /// The maximum number of non-terminated backends this drone will accept.
+    /// If not set, the drone does not advertise a limit.
+    #[serde(default)]
+    pub max_backends: Option<u32>,
+
     #[deprecated(
         since = "0.4.12",
         note = "Moved to `executor_config` (only applies to DockerRuntimeConfig)."
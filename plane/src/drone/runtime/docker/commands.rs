@@ -130,6 +130,26 @@ pub fn validate_mount_path(path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Checks that an environment variable name is a valid POSIX shell identifier.
+fn validate_env_name(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Spawn request contains invalid environment variable name {:?}",
+            name
+        ))
+    }
+}
+
 pub fn get_container_config_from_executor_config(
     backend_id: Option<&BackendName>,
     exec_config: DockerExecutorConfig,
@@ -139,6 +159,10 @@ pub fn get_container_config_from_executor_config(
     log_config: Option<&HostConfigLogConfig>,
     mount_base: Option<&PathBuf>,
 ) -> Result<bollard::container::Config<String>> {
+    for name in exec_config.env.keys() {
+        validate_env_name(name)?;
+    }
+
     let mut env = exec_config.env;
     env.insert("PORT".to_string(), CONTAINER_PORT.to_string());
 
@@ -205,6 +229,7 @@ pub fn get_container_config_from_executor_config(
         image: Some(exec_config.image.clone()),
         labels: Some(create_labels()),
         env: Some(env),
+        cmd: exec_config.args,
         exposed_ports: Some(
             vec![(format!("{}/tcp", CONTAINER_PORT), HashMap::new())]
                 .into_iter()
@@ -449,6 +474,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_env_and_args() {
+        let mut exec_config = DockerExecutorConfig::from_image_with_defaults(String::default());
+        exec_config
+            .env
+            .insert("MY_VAR_1".to_string(), "value".to_string());
+        exec_config.args = Some(vec!["--flag".to_string(), "value".to_string()]);
+
+        let config = get_container_config_from_executor_config(
+            None,
+            exec_config,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert!(config.env.unwrap().contains(&"MY_VAR_1=value".to_string()));
+        assert_eq!(
+            config.cmd,
+            Some(vec!["--flag".to_string(), "value".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_env_invalid_name() {
+        for name in ["", "1VAR", "MY-VAR", "MY VAR", "MY=VAR"] {
+            let mut exec_config = DockerExecutorConfig::from_image_with_defaults(String::default());
+            exec_config
+                .env
+                .insert(name.to_string(), "value".to_string());
+
+            let err = get_container_config_from_executor_config(
+                None,
+                exec_config,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("invalid environment variable name"));
+        }
+    }
+
     // Test invalid mount paths
 
     #[test]
@@ -22,7 +22,11 @@ use bollard::{
 };
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf, pin::Pin};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    pin::Pin,
+};
 use std::{
     net::SocketAddr,
     sync::{Arc, Mutex},
@@ -78,7 +82,7 @@ async fn events_loop(
         until: None,
         filters: vec![
             ("type", vec!["container"]),
-            ("event", vec!["die", "stop", "start"]),
+            ("event", vec!["die", "stop", "start", "oom"]),
             ("label", vec![PLANE_DOCKER_LABEL]),
         ]
         .into_iter()
@@ -86,6 +90,10 @@ async fn events_loop(
     };
     let mut stream = docker.events(Some(options));
 
+    // Docker sends an oom event before the die event of a container killed for
+    // exceeding its memory limit.
+    let mut oom_killed_backends: HashSet<BackendName> = HashSet::new();
+
     while let Some(e) = stream.next().await {
         let e: EventMessage = match e {
             Err(e) => {
@@ -121,6 +129,10 @@ async fn events_loop(
         if e.action.as_deref() == Some("start") {
             tracing::info!(?backend_id, "Received start event.");
 
+            // Forget an oom event from an earlier run of this container whose die event
+            // we never saw, so that it is not attributed to this run's exit.
+            oom_killed_backends.remove(&backend_id);
+
             let docker = docker.clone();
             let metrics_callback = metrics_callback.clone();
             tracing::info!(%backend_id, "Spawning metrics loop.");
@@ -131,6 +143,12 @@ async fn events_loop(
             continue;
         }
 
+        if e.action.as_deref() == Some("oom") {
+            tracing::warn!(?backend_id, "Received OOM event.");
+            oom_killed_backends.insert(backend_id);
+            continue;
+        }
+
         // By elimination, we know that the event is a stop/die event.
 
         let exit_code = attributes.get("exitCode");
@@ -142,9 +160,12 @@ async fn events_loop(
             "Received exit code"
         );
 
+        let oom_killed = oom_killed_backends.remove(&backend_id);
+
         if let Err(err) = event_sender.send(TerminateEvent {
             backend_id,
             exit_code,
+            oom_killed,
         }) {
             tracing::error!(?err, "Error sending event.");
         }
@@ -268,6 +289,9 @@ impl Runtime for DockerRuntime {
 pub struct TerminateEvent {
     pub backend_id: BackendName,
     pub exit_code: Option<i32>,
+    /// Whether the container was killed for exceeding its memory limit.
+    #[serde(default)]
+    pub oom_killed: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
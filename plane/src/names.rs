@@ -33,9 +33,18 @@ pub trait Name:
     fn prefix() -> Option<&'static str>;
 }
 
+/// Defines a name type. `$prefix`, if given, is required on every name of the type and
+/// is used for random names. Alternatively, `random_prefix = ...` only prefixes random
+/// names, for types that must also accept arbitrary (e.g. user-provided) names.
 #[macro_export]
 macro_rules! entity_name {
     ($name:ident, $prefix:expr) => {
+        $crate::entity_name!(@impl $name, $prefix, $prefix);
+    };
+    ($name:ident, $prefix:expr, random_prefix = $random_prefix:expr) => {
+        $crate::entity_name!(@impl $name, $prefix, $random_prefix);
+    };
+    (@impl $name:ident, $prefix:expr, $random_prefix:expr) => {
         #[derive(
             Debug,
             Clone,
@@ -54,7 +63,7 @@ macro_rules! entity_name {
             }
 
             fn new_random() -> Self {
-                if let Some(prefix) = $prefix {
+                if let Some(prefix) = $random_prefix {
                     Self($crate::util::random_prefixed_string(prefix))
                 } else {
                     Self($crate::util::random_string())
@@ -157,7 +166,12 @@ impl<T: Name> clap::builder::TypedValueParser for NameParser<T> {
 }
 
 entity_name!(ControllerName, Some("co"));
-entity_name!(BackendName, None::<&'static str>);
+// Backend names may be provided by the user, so only random names are prefixed.
+entity_name!(
+    BackendName,
+    None::<&'static str>,
+    random_prefix = Some("ba")
+);
 entity_name!(ProxyName, Some("px"));
 entity_name!(DroneName, Some("dr"));
 entity_name!(AcmeDnsServerName, Some("ns"));
@@ -292,6 +306,35 @@ mod tests {
         assert_eq!(Err(NameError::TooLong(100)), ControllerName::try_from(name));
     }
 
+    #[test]
+    fn test_random_backend_name() {
+        let name = BackendName::new_random();
+        assert!(name.to_string().starts_with("ba-"));
+        assert_eq!(Ok(name.clone()), BackendName::try_from(name.to_string()));
+    }
+
+    #[test]
+    fn test_unprefixed_backend_name() {
+        assert_eq!(
+            Ok(BackendName("abcd1234".to_string())),
+            BackendName::try_from("abcd1234".to_string())
+        );
+    }
+
+    #[test]
+    fn test_random_drone_name() {
+        let name = DroneName::new_random();
+        assert!(name.to_string().starts_with("dr-"));
+        assert_eq!(Ok(name.clone()), DroneName::try_from(name.to_string()));
+        assert_eq!(
+            Err(NameError::InvalidPrefix(
+                "ba-abcd".to_string(),
+                "dr".to_string()
+            )),
+            DroneName::try_from("ba-abcd".to_string())
+        );
+    }
+
     #[test]
     fn test_backend_name_from_invalid_container_id() {
         let container_id = ContainerId::from("invalid-123".to_string());
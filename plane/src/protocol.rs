@@ -226,6 +226,15 @@ pub enum MessageFromProxy {
     RouteInfoRequest(RouteInfoRequest),
     KeepAlive(BackendName),
     CertManagerRequest(CertManagerRequest),
+    /// The number of open connections to a backend through this proxy. Sent after each
+    /// KeepAlive; controllers that predate this message skip it, since they cannot parse it.
+    ConnectionStats(BackendConnectionStats),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BackendConnectionStats {
+    pub backend: BackendName,
+    pub active_connections: u32,
 }
 
 impl ChannelMessage for MessageFromProxy {
@@ -37,6 +37,54 @@ const FAILURE_SLEEP_TIME: Duration = Duration::from_secs(60 * 5); // 5 minutes
 /// How long in advance of the certificate expiring to renew it.
 const RENEWAL_WINDOW: Duration = Duration::from_secs(24 * 60 * 60 * 30); // 30 days
 
+/// How long to wait for the controller to respond to a cert manager request.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to sleep after a cert lease request gets no usable response.
+const NO_RESPONSE_SLEEP_TIME: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+enum CertManagerResponseError {
+    #[error("Timed out waiting for response from cert manager.")]
+    Timeout,
+
+    #[error("Cert manager channel error: {0}")]
+    Channel(#[from] broadcast::error::RecvError),
+}
+
+/// Send a request to the cert manager and wait for its response, giving up after
+/// RESPONSE_TIMEOUT. Without a timeout, a request lost while the controller connection
+/// is down would stall the refresh loop indefinitely.
+///
+/// A response to an earlier request that timed out may still arrive later, so any
+/// responses already queued are discarded before sending, rather than being taken
+/// as the response to this request.
+async fn send_request(
+    request_sender: &(impl Fn(CertManagerRequest) + Send + Sync + 'static),
+    response_receiver: &mut broadcast::Receiver<CertManagerResponse>,
+    request: CertManagerRequest,
+) -> Result<CertManagerResponse, CertManagerResponseError> {
+    loop {
+        match response_receiver.try_recv() {
+            Ok(response) => {
+                tracing::warn!(
+                    response = response.as_value(),
+                    "Discarding stale response from cert manager."
+                );
+            }
+            Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+            Err(_) => break,
+        }
+    }
+
+    request_sender(request);
+
+    match tokio::time::timeout(RESPONSE_TIMEOUT, response_receiver.recv()).await {
+        Ok(response) => Ok(response?),
+        Err(_) => Err(CertManagerResponseError::Timeout),
+    }
+}
+
 /// Handle for receiving new certificates. Implements ResolvesServerCert, which
 /// allows it to be used as a rustls cert_resolver.
 pub struct CertWatcher {
@@ -277,13 +325,17 @@ async fn refresh_loop_step(
     }
 
     tracing::info!("Requesting certificate lease.");
-    request_sender(CertManagerRequest::CertLeaseRequest);
-
-    // wait for response.
-    let response = match response_receiver.recv().await {
+    let response = match send_request(
+        request_sender,
+        response_receiver,
+        CertManagerRequest::CertLeaseRequest,
+    )
+    .await
+    {
         Ok(response) => response,
         Err(err) => {
-            tracing::error!(?err, "Cert manager error.");
+            tracing::error!(?err, "Cert manager error. Sleeping.");
+            tokio::time::sleep(NO_RESPONSE_SLEEP_TIME).await;
             return Ok(());
         }
     };
@@ -296,7 +348,8 @@ async fn refresh_loop_step(
             return Ok(());
         }
         _ => {
-            tracing::error!("Unexpected response from cert manager.");
+            tracing::error!("Unexpected response from cert manager. Sleeping.");
+            tokio::time::sleep(NO_RESPONSE_SLEEP_TIME).await;
             return Ok(());
         }
     }
@@ -305,6 +358,10 @@ async fn refresh_loop_step(
 
     let result = get_certificate(account, cluster, request_sender, response_receiver).await;
 
+    // Whether or not the order succeeded, the TXT record is no longer needed, and
+    // releasing the lease lets another proxy retry immediately.
+    request_sender(CertManagerRequest::ReleaseCertLease);
+
     match result {
         Ok(cert_pair) => {
             tracing::info!("Got certificate.");
@@ -354,8 +411,9 @@ async fn get_certificate(
     response_receiver: &mut broadcast::Receiver<CertManagerResponse>,
 ) -> anyhow::Result<CertificatePair> {
     let mut builder = OrderBuilder::new(account);
-    builder.add_dns_identifier(format!("{}", cluster));
-    builder.add_dns_identifier(format!("*.{}", cluster)); // wildcard
+    // Certificates are issued for hostnames, so any port in the cluster name is dropped.
+    builder.add_dns_identifier(cluster.host().to_string());
+    builder.add_dns_identifier(format!("*.{}", cluster.host())); // wildcard
     let order = builder.build().await.context("Building order")?;
 
     let authorizations = order
@@ -377,14 +435,17 @@ async fn get_certificate(
 
         tracing::info!(txt_value, "Requesting TXT record from platform.");
 
-        request_sender(CertManagerRequest::SetTxtRecord { txt_value });
-        tracing::info!("Waiting for response from cert manager.");
-
-        let response = match response_receiver.recv().await {
+        let response = match send_request(
+            request_sender,
+            response_receiver,
+            CertManagerRequest::SetTxtRecord { txt_value },
+        )
+        .await
+        {
             Ok(response) => response,
             Err(err) => {
                 tracing::error!(?err, "Cert manager error.");
-                return Err(anyhow!("Cert manager error."));
+                return Err(anyhow!("Cert manager error: {}", err));
             }
         };
         tracing::info!(
@@ -480,3 +541,38 @@ async fn get_certificate(
 
     Ok(cert_pair)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stale_response_is_not_taken_as_response_to_next_request() {
+        let (response_sender, mut response_receiver) = broadcast::channel(8);
+
+        // A response to an earlier request that timed out.
+        response_sender
+            .send(CertManagerResponse::SetTxtRecordResponse { accepted: true })
+            .unwrap();
+
+        let request_sender = move |request| {
+            assert!(matches!(request, CertManagerRequest::CertLeaseRequest));
+            response_sender
+                .send(CertManagerResponse::CertLeaseResponse { accepted: false })
+                .unwrap();
+        };
+
+        let response = send_request(
+            &request_sender,
+            &mut response_receiver,
+            CertManagerRequest::CertLeaseRequest,
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            response,
+            CertManagerResponse::CertLeaseResponse { accepted: false }
+        ));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     names::{OrRandom, ProxyName},
-    proxy::{AcmeConfig, AcmeEabConfiguration, ServerPortConfig},
+    proxy::{rate_limit::RateLimitConfig, AcmeConfig, AcmeEabConfiguration, ServerPortConfig},
     types::ClusterName,
 };
 use anyhow::{anyhow, Result};
@@ -14,6 +14,15 @@ const LOCAL_HTTP_PORT: u16 = 9090;
 const PROD_HTTP_PORT: u16 = 80;
 const PROD_HTTPS_PORT: u16 = 443;
 
+/// Let's Encrypt's production ACME directory. Used if --acme-email is provided without --acme-endpoint.
+pub const LETS_ENCRYPT_PRODUCTION_DIRECTORY: &str =
+    "https://acme-v02.api.letsencrypt.org/directory";
+
+/// Let's Encrypt's staging ACME directory. It has much higher rate limits, but issues
+/// certificates that are not trusted by browsers, so it is only useful for testing.
+pub const LETS_ENCRYPT_STAGING_DIRECTORY: &str =
+    "https://acme-staging-v02.api.letsencrypt.org/directory";
+
 #[derive(Parser)]
 pub struct ProxyOpts {
     #[clap(long)]
@@ -37,6 +46,8 @@ pub struct ProxyOpts {
     #[clap(long)]
     cert_path: Option<PathBuf>,
 
+    /// ACME directory URL. Defaults to Let's Encrypt's production directory if --acme-email
+    /// is provided. Use https://acme-staging-v02.api.letsencrypt.org/directory for testing.
     #[clap(long)]
     acme_endpoint: Option<Url>,
 
@@ -54,6 +65,21 @@ pub struct ProxyOpts {
     /// URL to redirect the root path to.
     #[clap(long)]
     root_redirect_url: Option<Url>,
+
+    /// Maximum sustained rate of proxied requests per second for this cluster.
+    /// Requests beyond the limit receive a 429 response. Unlimited if omitted.
+    #[clap(long)]
+    rate_limit: Option<f64>,
+
+    /// Number of requests allowed in a burst above --rate-limit. Defaults to
+    /// the rate limit rounded up.
+    #[clap(long, requires = "rate_limit")]
+    rate_limit_burst: Option<u32>,
+
+    /// Apply --rate-limit to each client IP separately, instead of to all
+    /// clients of the cluster together.
+    #[clap(long, requires = "rate_limit")]
+    rate_limit_per_ip: bool,
 }
 
 impl ProxyOpts {
@@ -99,11 +125,13 @@ impl ProxyOpts {
                     "Must specify --acme-email when using --acme-endpoint."
                 ));
             }
-            (None, Some(_)) => {
-                return Err(anyhow!(
-                    "Must specify --acme-endpoint when using --acme-email."
-                ));
-            }
+            (None, Some(email)) => Some(AcmeConfig {
+                endpoint: Url::parse(LETS_ENCRYPT_PRODUCTION_DIRECTORY)
+                    .expect("Let's Encrypt directory URL is valid."),
+                mailto_email: email,
+                acme_eab_keypair,
+                accept_insecure_certs_for_testing: false,
+            }),
             (Some(endpoint), Some(email)) => Some(AcmeConfig {
                 endpoint,
                 mailto_email: email,
@@ -113,6 +141,19 @@ impl ProxyOpts {
             (None, None) => None,
         };
 
+        let rate_limit = match self.rate_limit {
+            Some(requests_per_second) if requests_per_second > 0.0 => Some(RateLimitConfig {
+                requests_per_second,
+                burst: self
+                    .rate_limit_burst
+                    .unwrap_or(requests_per_second.ceil() as u32)
+                    .max(1),
+                per_ip: self.rate_limit_per_ip,
+            }),
+            Some(_) => return Err(anyhow!("--rate-limit must be greater than zero.")),
+            None => None,
+        };
+
         Ok(ProxyConfig {
             name,
             controller_url: self.controller_url,
@@ -121,6 +162,96 @@ impl ProxyOpts {
             port_config,
             acme_config,
             root_redirect_url: self.root_redirect_url,
+            rate_limit,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> ProxyConfig {
+        let mut all_args = vec![
+            "proxy",
+            "--controller-url",
+            "http://localhost:8080",
+            "--cluster",
+            "plane.test",
+        ];
+        all_args.extend_from_slice(args);
+        ProxyOpts::try_parse_from(all_args)
+            .unwrap()
+            .into_config()
+            .unwrap()
+    }
+
+    #[test]
+    fn acme_endpoint_defaults_to_production() {
+        let config = parse(&["--acme-email", "admin@plane.test"]);
+        let acme_config = config.acme_config.unwrap();
+        assert_eq!(
+            acme_config.endpoint.as_str(),
+            LETS_ENCRYPT_PRODUCTION_DIRECTORY
+        );
+    }
+
+    #[test]
+    fn acme_endpoint_uses_configured_url() {
+        let config = parse(&[
+            "--acme-email",
+            "admin@plane.test",
+            "--acme-endpoint",
+            LETS_ENCRYPT_STAGING_DIRECTORY,
+        ]);
+        let acme_config = config.acme_config.unwrap();
+        assert_eq!(
+            acme_config.endpoint.as_str(),
+            LETS_ENCRYPT_STAGING_DIRECTORY
+        );
+    }
+
+    #[test]
+    fn no_acme_config_without_email() {
+        let config = parse(&[]);
+        assert!(config.acme_config.is_none());
+    }
+
+    #[test]
+    fn rate_limit_defaults_to_unlimited() {
+        let config = parse(&[]);
+        assert!(config.rate_limit.is_none());
+    }
+
+    #[test]
+    fn rate_limit_burst_defaults_to_rate() {
+        let config = parse(&["--rate-limit", "2.5"]);
+        assert_eq!(
+            config.rate_limit,
+            Some(RateLimitConfig {
+                requests_per_second: 2.5,
+                burst: 3,
+                per_ip: false,
+            })
+        );
+    }
+
+    #[test]
+    fn rate_limit_with_burst_and_per_ip() {
+        let config = parse(&[
+            "--rate-limit",
+            "10",
+            "--rate-limit-burst",
+            "50",
+            "--rate-limit-per-ip",
+        ]);
+        assert_eq!(
+            config.rate_limit,
+            Some(RateLimitConfig {
+                requests_per_second: 10.0,
+                burst: 50,
+                per_ip: true,
+            })
+        );
+    }
+}
@@ -6,7 +6,8 @@ use std::{
 };
 use tokio::task::JoinHandle;
 
-type BackendNameListener = Box<dyn Fn(&BackendName) + Send + Sync + 'static>;
+/// Called with a backend and its number of active connections.
+type BackendNameListener = Box<dyn Fn(&BackendName, u32) + Send + Sync + 'static>;
 
 #[derive(Debug)]
 pub struct BackendEntry {
@@ -27,7 +28,7 @@ pub struct ConnectionMonitor {
 impl ConnectionMonitor {
     pub fn set_listener<F>(&mut self, listener: F)
     where
-        F: Fn(&BackendName) + Send + Sync + 'static,
+        F: Fn(&BackendName, u32) + Send + Sync + 'static,
     {
         self.listener = Some(Box::new(listener));
     }
@@ -39,7 +40,7 @@ impl ConnectionMonitor {
             }
             Entry::Vacant(entry) => {
                 if let Some(listener) = &self.listener {
-                    listener(backend_id);
+                    listener(backend_id, 0);
                 }
                 self.visit_queue
                     .push_back((SystemTime::now() + HEARTBEAT_INTERVAL, backend_id.clone()));
@@ -60,7 +61,7 @@ impl ConnectionMonitor {
             }
             Entry::Vacant(entry) => {
                 if let Some(listener) = &self.listener {
-                    listener(backend_id);
+                    listener(backend_id, 1);
                 }
 
                 self.visit_queue
@@ -86,6 +87,42 @@ impl ConnectionMonitor {
             }
         }
     }
+
+    /// Reports the backend's connection count to the listener and schedules its next
+    /// visit, or forgets the backend if it has been idle since the last visit.
+    fn visit(&mut self, backend: BackendName) {
+        let Some(backend_entry) = self.backends.get_mut(&backend) else {
+            // This shouldn't happen.
+            return;
+        };
+
+        if backend_entry.active_connections > 0 || backend_entry.had_recent_connection {
+            backend_entry.had_recent_connection = false;
+            let active_connections = backend_entry.active_connections;
+
+            if let Some(listener) = &self.listener {
+                listener(&backend, active_connections);
+            }
+
+            self.visit_queue
+                .push_back((SystemTime::now() + HEARTBEAT_INTERVAL, backend));
+        } else {
+            // The backend has no connections and has not been touched recently, so we can
+            // remove it. Report that its connections have closed first, since the last
+            // report may have been made while some were still open.
+            if let Some(listener) = &self.listener {
+                listener(&backend, 0);
+            }
+            self.backends.remove(&backend);
+        }
+    }
+
+    /// The number of open connections to the backend through this proxy.
+    pub fn active_connections(&self, backend_id: &BackendName) -> u32 {
+        self.backends
+            .get(backend_id)
+            .map_or(0, |entry| entry.active_connections)
+    }
 }
 
 pub struct ConnectionMonitorHandle {
@@ -122,27 +159,10 @@ impl ConnectionMonitorHandle {
                         .await;
                     }
 
-                    let mut monitor_lock = monitor.lock().expect("Monitor lock was poisoned.");
-
-                    let Some(backend_entry) = monitor_lock.backends.get_mut(&backend) else {
-                        // This shouldn't happen.
-                        continue;
-                    };
-
-                    if backend_entry.active_connections > 0 || backend_entry.had_recent_connection {
-                        backend_entry.had_recent_connection = false;
-
-                        if let Some(listener) = &monitor_lock.listener {
-                            listener(&backend);
-                        }
-
-                        monitor_lock
-                            .visit_queue
-                            .push_back((SystemTime::now() + HEARTBEAT_INTERVAL, backend));
-                    } else {
-                        // The backend has no connections and has not been touched recently, so we can remove it.
-                        monitor_lock.backends.remove(&backend);
-                    }
+                    monitor
+                        .lock()
+                        .expect("Monitor lock was poisoned.")
+                        .visit(backend);
                 }
             })
         };
@@ -155,7 +175,7 @@ impl ConnectionMonitorHandle {
 
     pub fn set_listener<F>(&self, listener: F)
     where
-        F: Fn(&BackendName) + Send + Sync + 'static,
+        F: Fn(&BackendName, u32) + Send + Sync + 'static,
     {
         self.monitor
             .lock()
@@ -176,3 +196,65 @@ impl Drop for ConnectionMonitorHandle {
         self.handle.abort();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::names::Name;
+
+    #[test]
+    fn connections_move_counter() {
+        let reported: Arc<Mutex<Vec<u32>>> = Arc::default();
+        let mut monitor = ConnectionMonitor::default();
+        {
+            let reported = reported.clone();
+            monitor.set_listener(move |_, active_connections| {
+                reported.lock().unwrap().push(active_connections);
+            });
+        }
+
+        let backend = BackendName::new_random();
+        assert_eq!(monitor.active_connections(&backend), 0);
+
+        monitor.inc_connection(&backend);
+        monitor.inc_connection(&backend);
+        assert_eq!(monitor.active_connections(&backend), 2);
+
+        monitor.dec_connection(&backend);
+        assert_eq!(monitor.active_connections(&backend), 1);
+
+        monitor.dec_connection(&backend);
+        assert_eq!(monitor.active_connections(&backend), 0);
+
+        // The listener is called when the backend is first seen.
+        assert_eq!(*reported.lock().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn zero_reported_when_idle_backend_is_removed() {
+        let reported: Arc<Mutex<Vec<u32>>> = Arc::default();
+        let mut monitor = ConnectionMonitor::default();
+        {
+            let reported = reported.clone();
+            monitor.set_listener(move |_, active_connections| {
+                reported.lock().unwrap().push(active_connections);
+            });
+        }
+
+        let backend = BackendName::new_random();
+        monitor.inc_connection(&backend);
+
+        // The connection is still open at the first visit, so another is scheduled.
+        let (_, visited) = monitor.visit_queue.pop_front().unwrap();
+        monitor.visit(visited);
+
+        // The connection closes before the next visit, which removes the backend.
+        monitor.dec_connection(&backend);
+        let (_, visited) = monitor.visit_queue.pop_front().unwrap();
+        monitor.visit(visited);
+        assert!(!monitor.backends.contains_key(&backend));
+        assert!(monitor.visit_queue.is_empty());
+
+        assert_eq!(*reported.lock().unwrap(), vec![1, 1, 0]);
+    }
+}
@@ -2,12 +2,14 @@ use self::proxy_connection::ProxyConnection;
 use crate::names::ProxyName;
 use crate::proxy::cert_manager::watcher_manager_pair;
 use crate::proxy::proxy_service::ProxyMakeService;
+use crate::proxy::rate_limit::{RateLimitConfig, RateLimiter};
 use crate::proxy::shutdown_signal::ShutdownSignal;
 use crate::{client::PlaneClient, signals::wait_for_shutdown_signal, types::ClusterName};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
 use std::path::PathBuf;
+use std::sync::Arc;
 use url::Url;
 
 pub mod cert_manager;
@@ -16,6 +18,7 @@ pub mod command;
 mod connection_monitor;
 pub mod proxy_connection;
 mod proxy_service;
+pub mod rate_limit;
 mod rewriter;
 mod route_map;
 mod shutdown_signal;
@@ -107,6 +110,8 @@ pub struct ProxyConfig {
     pub port_config: ServerPortConfig,
     pub acme_config: Option<AcmeConfig>,
     pub root_redirect_url: Option<Url>,
+    /// Limit on the rate of proxied requests. If None, requests are not rate limited.
+    pub rate_limit: Option<RateLimitConfig>,
 }
 
 pub async fn run_proxy(config: ProxyConfig) -> Result<()> {
@@ -123,6 +128,9 @@ pub async fn run_proxy(config: ProxyConfig) -> Result<()> {
     let shutdown_signal = ShutdownSignal::new();
 
     let https_redirect = config.port_config.https_port.is_some();
+    let rate_limiter = config
+        .rate_limit
+        .map(|rate_limit| Arc::new(RateLimiter::new(rate_limit)));
 
     if config.port_config.https_port.is_some() {
         cert_watcher.wait_for_initial_cert().await?;
@@ -132,6 +140,7 @@ pub async fn run_proxy(config: ProxyConfig) -> Result<()> {
         state: proxy_connection.state(),
         https_redirect,
         root_redirect_url: config.root_redirect_url.clone(),
+        rate_limiter: rate_limiter.clone(),
     }
     .serve_http(config.port_config.http_port, shutdown_signal.subscribe())?;
 
@@ -142,6 +151,7 @@ pub async fn run_proxy(config: ProxyConfig) -> Result<()> {
             state: proxy_connection.state(),
             https_redirect: false,
             root_redirect_url: config.root_redirect_url,
+            rate_limiter,
         }
         .serve_https(https_port, cert_watcher, shutdown_signal.subscribe())?;
 
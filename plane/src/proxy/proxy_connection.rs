@@ -2,7 +2,7 @@ use super::{cert_manager::CertManager, proxy_service::ProxyState};
 use crate::{
     client::PlaneClient,
     names::ProxyName,
-    protocol::{MessageFromProxy, MessageToProxy, RouteInfoRequest},
+    protocol::{BackendConnectionStats, MessageFromProxy, MessageToProxy, RouteInfoRequest},
     types::ClusterName,
 };
 use std::sync::Arc;
@@ -46,12 +46,21 @@ impl ProxyConnection {
                             tracing::error!(?e, "Error sending route info request.");
                         }
                     });
-                    let sender = conn.sender(MessageFromProxy::KeepAlive);
-                    state.monitor.set_listener(move |backend| {
-                        if let Err(err) = sender.send(backend.clone()) {
-                            tracing::error!(?err, "Error sending keepalive.");
-                        }
-                    });
+                    let keepalive_sender = conn.sender(MessageFromProxy::KeepAlive);
+                    let stats_sender = conn.sender(MessageFromProxy::ConnectionStats);
+                    state
+                        .monitor
+                        .set_listener(move |backend, active_connections| {
+                            if let Err(err) = keepalive_sender.send(backend.clone()) {
+                                tracing::error!(?err, "Error sending keepalive.");
+                            }
+                            if let Err(err) = stats_sender.send(BackendConnectionStats {
+                                backend: backend.clone(),
+                                active_connections,
+                            }) {
+                                tracing::error!(?err, "Error sending connection stats.");
+                            }
+                        });
 
                     while let Some(message) = conn.recv().await {
                         match message {
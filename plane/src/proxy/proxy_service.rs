@@ -1,4 +1,5 @@
 use super::connection_monitor::ConnectionMonitorHandle;
+use super::rate_limit::RateLimiter;
 use super::rewriter::RequestRewriterError;
 use super::route_map::RouteMap;
 use super::tls::TlsStream;
@@ -10,14 +11,17 @@ use crate::proxy::tls::TlsAcceptor;
 use crate::SERVER_NAME;
 use axum::http::uri::PathAndQuery;
 use futures_util::{Future, FutureExt};
+use hyper::body::HttpBody;
 use hyper::server::conn::AddrIncoming;
 use hyper::{
     client::HttpConnector, server::conn::AddrStream, service::Service, Body, Request, Response,
+    Version,
 };
 use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::{atomic::AtomicBool, Arc};
+use std::time::Duration;
 use std::{
     future::ready,
     io::ErrorKind,
@@ -72,6 +76,9 @@ pub enum ProxyError {
     #[error("Invalid subdomain")]
     InvalidSubdomain,
 
+    #[error("Rate limit exceeded (retry after {0:?})")]
+    RateLimited(Duration),
+
     #[error("HTTP error: {0}")]
     HttpError(#[from] hyper::http::Error),
 
@@ -99,6 +106,8 @@ impl From<RequestRewriterError> for ProxyError {
 pub struct ProxyState {
     pub route_map: RouteMap,
     http_client: hyper::Client<HttpConnector>,
+    /// Client for cleartext HTTP/2 (h2c) upstream requests, used for gRPC.
+    h2c_client: hyper::Client<HttpConnector>,
     pub monitor: ConnectionMonitorHandle,
     connected: AtomicBool,
 }
@@ -114,6 +123,9 @@ impl ProxyState {
         Self {
             route_map: RouteMap::new(),
             http_client: hyper::Client::builder().build_http::<hyper::Body>(),
+            h2c_client: hyper::Client::builder()
+                .http2_only(true)
+                .build_http::<hyper::Body>(),
             monitor: ConnectionMonitorHandle::new(),
             connected: AtomicBool::new(false),
         }
@@ -127,6 +139,80 @@ impl ProxyState {
     pub fn connected(&self) -> bool {
         self.connected.load(std::sync::atomic::Ordering::Relaxed)
     }
+
+    /// Sends a (non-upgrade) request to a backend. gRPC requests arriving over HTTP/2 are
+    /// sent to the backend over h2c, since gRPC depends on HTTP/2 framing and trailers.
+    /// Everything else is sent over HTTP/1.1, which all backends are expected to speak.
+    /// Backends are not required to support HTTP/2, so other requests that arrive over
+    /// HTTP/2 are downgraded; hyper refuses to send an HTTP/2 request over an HTTP/1
+    /// connection.
+    async fn forward_request(
+        &self,
+        mut req: Request<Body>,
+        backend_id: &BackendName,
+    ) -> Result<Response<Body>, ProxyError> {
+        if req.version() == Version::HTTP_2 && is_grpc(&req) {
+            let response = self
+                .h2c_client
+                .request(req)
+                .await
+                .map_err(|e| ProxyError::RequestError(e, backend_id.clone()))?;
+            return Ok(self.monitor_stream(response, backend_id));
+        }
+
+        *req.version_mut() = Version::HTTP_11;
+        self.http_client
+            .request(req)
+            .await
+            .map_err(|e| ProxyError::RequestError(e, backend_id.clone()))
+    }
+
+    /// Counts a streamed response as an active connection to the backend until its body
+    /// (including trailers) has been passed through to the client.
+    fn monitor_stream(&self, response: Response<Body>, backend_id: &BackendName) -> Response<Body> {
+        let (parts, mut upstream) = response.into_parts();
+        let (mut sender, body) = Body::channel();
+        let monitor = self.monitor.monitor();
+        let backend_id = backend_id.clone();
+
+        monitor
+            .lock()
+            .expect("Monitor lock was poisoned.")
+            .inc_connection(&backend_id);
+
+        tokio::spawn(async move {
+            if let Err(error) = pipe_body(&mut upstream, &mut sender).await {
+                tracing::info!(?error, "Streamed response ended early.");
+                sender.abort();
+            }
+
+            monitor
+                .lock()
+                .expect("Monitor lock was poisoned.")
+                .dec_connection(&backend_id);
+        });
+
+        Response::from_parts(parts, body)
+    }
+}
+
+fn is_grpc(req: &Request<Body>) -> bool {
+    req.headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/grpc"))
+}
+
+async fn pipe_body(upstream: &mut Body, sender: &mut hyper::body::Sender) -> hyper::Result<()> {
+    while let Some(chunk) = upstream.data().await {
+        sender.send_data(chunk?).await?;
+    }
+
+    if let Some(trailers) = upstream.trailers().await? {
+        sender.send_trailers(trailers).await?;
+    }
+
+    Ok(())
 }
 
 struct RequestHandler {
@@ -134,6 +220,7 @@ struct RequestHandler {
     https_redirect: bool,
     remote_meta: ForwardableRequestInfo,
     root_redirect_url: Option<Url>,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl RequestHandler {
@@ -144,6 +231,16 @@ impl RequestHandler {
         let result = self.handle_request_inner(req).await;
         match result {
             Ok(response) => Ok(response),
+            Err(ProxyError::RateLimited(retry_after)) => {
+                // Retry-After is in whole seconds, so round up.
+                let retry_after_secs = retry_after.as_secs_f64().ceil() as u64;
+                Ok(response_builder()
+                    .status(hyper::StatusCode::TOO_MANY_REQUESTS)
+                    .header(hyper::header::SERVER, SERVER_NAME)
+                    .header(hyper::header::RETRY_AFTER, retry_after_secs.max(1))
+                    .body(hyper::Body::from("Too many requests"))
+                    .expect("Static response is always valid"))
+            }
             Err(err) => {
                 let (status_code, body) = match err {
                     ProxyError::InvalidConnectionToken => (
@@ -195,10 +292,12 @@ impl RequestHandler {
         }
 
         if self.https_redirect {
+            // HTTP/2 requests carry the host in the URI's authority instead of a Host header.
             let Some(host) = req
                 .headers()
                 .get(hyper::header::HOST)
                 .and_then(|value| value.to_str().ok())
+                .or_else(|| req.uri().authority().map(|authority| authority.as_str()))
             else {
                 return Err(ProxyError::MissingHostHeader);
             };
@@ -273,6 +372,12 @@ impl RequestHandler {
             return Err(ProxyError::InvalidSubdomain);
         }
 
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter
+                .check(&route_info.cluster, self.remote_meta.ip)
+                .map_err(ProxyError::RateLimited)?;
+        }
+
         let backend_id = route_info.backend_id.clone();
         request_rewriter.set_authority(route_info.address.0);
 
@@ -335,11 +440,7 @@ impl RequestHandler {
         } else {
             let req = request_rewriter.into_request(&route_info);
             self.state.monitor.touch_backend(&backend_id);
-            self.state
-                .http_client
-                .request(req)
-                .await
-                .map_err(|e| ProxyError::RequestError(e, backend_id.clone()))?
+            self.state.forward_request(req, &backend_id).await?
         };
 
         let headers = response.headers_mut();
@@ -401,6 +502,7 @@ pub struct ProxyMakeService {
     pub state: Arc<ProxyState>,
     pub https_redirect: bool,
     pub root_redirect_url: Option<Url>,
+    pub rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl ProxyMakeService {
@@ -429,10 +531,12 @@ impl ProxyMakeService {
     where
         F: Future<Output = ()> + Send + 'static,
     {
-        let server_config = ServerConfig::builder()
+        let mut server_config = ServerConfig::builder()
             .with_safe_defaults()
             .with_no_client_auth()
             .with_cert_resolver(Arc::new(cert_watcher));
+        // Offer HTTP/2 so that gRPC clients can connect over TLS.
+        server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
 
         let addr: SocketAddr = ([0, 0, 0, 0], port).into();
         let incoming = AddrIncoming::bind(&addr).map_err(ProxyError::BindError)?;
@@ -470,6 +574,7 @@ impl<'a> Service<&'a AddrStream> for ProxyMakeService {
                 protocol: Protocol::Http,
             },
             root_redirect_url: self.root_redirect_url.clone(),
+            rate_limiter: self.rate_limiter.clone(),
         });
         ready(Ok(ProxyService { handler })).boxed()
     }
@@ -494,7 +599,227 @@ impl<'a> Service<&'a TlsStream> for ProxyMakeService {
                 protocol: Protocol::Https,
             },
             root_redirect_url: self.root_redirect_url.clone(),
+            rate_limiter: self.rate_limiter.clone(),
         });
         ready(Ok(ProxyService { handler })).boxed()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        log_types::BackendAddr,
+        protocol::{RouteInfo, RouteInfoResponse},
+        types::{BearerToken, ClusterName, SecretToken},
+    };
+    use hyper::{service::service_fn, HeaderMap};
+    use std::{str::FromStr, time::Duration};
+
+    const TOKEN: &str = "h2-test-token";
+
+    fn backend_id() -> BackendName {
+        BackendName::try_from("ba-test".to_string()).unwrap()
+    }
+
+    fn grpc_trailers() -> HeaderMap {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+        trailers
+    }
+
+    /// Handles requests like a gRPC server: /Unary returns one message, /Stream returns
+    /// three messages with a delay between them. Both end with grpc-status trailers.
+    async fn grpc_handler(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        assert_eq!(req.version(), Version::HTTP_2);
+        let (mut sender, body) = Body::channel();
+        let streaming = req.uri().path().ends_with("/Stream");
+
+        tokio::spawn(async move {
+            let messages = if streaming { 3 } else { 1 };
+            for i in 0..messages {
+                if i > 0 {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                sender
+                    .send_data(format!("message {}", i).into())
+                    .await
+                    .unwrap();
+            }
+            sender.send_trailers(grpc_trailers()).await.unwrap();
+        });
+
+        Ok(Response::builder()
+            .header(hyper::header::CONTENT_TYPE, "application/grpc")
+            .body(body)
+            .unwrap())
+    }
+
+    async fn start_backend(http2_only: bool) -> SocketAddr {
+        let make_service = hyper::service::make_service_fn(move |_| async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| async move {
+                if http2_only {
+                    grpc_handler(req).await
+                } else {
+                    assert_eq!(req.version(), Version::HTTP_11);
+                    Ok(Response::new(Body::from("hello")))
+                }
+            }))
+        });
+
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into())
+            .http2_only(http2_only)
+            .serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    fn request(addr: SocketAddr, path: &str, grpc: bool) -> Request<Body> {
+        let mut builder = Request::builder()
+            .version(Version::HTTP_2)
+            .uri(format!("http://{}{}", addr, path));
+        if grpc {
+            builder = builder.header(hyper::header::CONTENT_TYPE, "application/grpc");
+        }
+        builder.body(Body::from("request")).unwrap()
+    }
+
+    /// Starts a proxy that only speaks HTTP/2, as it does once h2 is negotiated over TLS,
+    /// with a route from TOKEN to the backend at `backend_addr`.
+    async fn start_proxy(backend_addr: SocketAddr) -> SocketAddr {
+        let state = Arc::new(ProxyState::new());
+        state.route_map.receive(RouteInfoResponse {
+            token: BearerToken::from(TOKEN.to_string()),
+            route_info: Some(RouteInfo {
+                backend_id: backend_id(),
+                address: BackendAddr(backend_addr),
+                secret_token: SecretToken::from("secret".to_string()),
+                cluster: ClusterName::from_str("127.0.0.1").unwrap(),
+                user: None,
+                user_data: None,
+                subdomain: None,
+            }),
+        });
+
+        let make_service = ProxyMakeService {
+            state,
+            https_redirect: false,
+            root_redirect_url: None,
+            rate_limiter: None,
+        };
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into())
+            .http2_only(true)
+            .serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    /// Sends a request to the proxy over HTTP/2. hyper sends no Host header over HTTP/2,
+    /// only :authority, like browsers and gRPC clients.
+    async fn send_h2(proxy_addr: SocketAddr, path: &str, grpc: bool) -> Response<Body> {
+        let client = hyper::Client::builder()
+            .http2_only(true)
+            .build_http::<Body>();
+        let path = format!("/{}{}", TOKEN, path);
+        client
+            .request(request(proxy_addr, &path, grpc))
+            .await
+            .unwrap()
+    }
+
+    fn active_connections(state: &ProxyState) -> u32 {
+        state
+            .monitor
+            .monitor()
+            .lock()
+            .unwrap()
+            .active_connections(&backend_id())
+    }
+
+    #[tokio::test]
+    async fn grpc_unary_over_h2c() {
+        let addr = start_backend(true).await;
+        let state = ProxyState::new();
+
+        let response = state
+            .forward_request(request(addr, "/test.Service/Unary", true), &backend_id())
+            .await
+            .unwrap();
+        let mut body = response.into_body();
+
+        assert_eq!(body.data().await.unwrap().unwrap(), "message 0");
+        assert!(body.data().await.is_none());
+        assert_eq!(body.trailers().await.unwrap(), Some(grpc_trailers()));
+    }
+
+    #[tokio::test]
+    async fn grpc_server_streaming_over_h2c() {
+        let addr = start_backend(true).await;
+        let state = ProxyState::new();
+
+        let response = state
+            .forward_request(request(addr, "/test.Service/Stream", true), &backend_id())
+            .await
+            .unwrap();
+        let mut body = response.into_body();
+
+        // The open stream counts as an active connection.
+        assert_eq!(active_connections(&state), 1);
+
+        for i in 0..3 {
+            let chunk = body.data().await.unwrap().unwrap();
+            assert_eq!(chunk, format!("message {}", i));
+        }
+        assert!(body.data().await.is_none());
+        assert_eq!(body.trailers().await.unwrap(), Some(grpc_trailers()));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(active_connections(&state), 0);
+    }
+
+    #[tokio::test]
+    async fn non_grpc_http2_request_uses_http1() {
+        let addr = start_backend(false).await;
+        let state = ProxyState::new();
+
+        let response = state
+            .forward_request(request(addr, "/", false), &backend_id())
+            .await
+            .unwrap();
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body, "hello");
+    }
+
+    #[tokio::test]
+    async fn grpc_request_over_h2_is_routed_by_authority() {
+        let backend_addr = start_backend(true).await;
+        let proxy_addr = start_proxy(backend_addr).await;
+
+        let response = send_h2(proxy_addr, "/test.Service/Unary", true).await;
+        assert_eq!(response.status(), hyper::StatusCode::OK);
+        assert_eq!(
+            response.headers()[PLANE_BACKEND_ID_HEADER],
+            backend_id().to_string()
+        );
+
+        let mut body = response.into_body();
+        assert_eq!(body.data().await.unwrap().unwrap(), "message 0");
+        assert!(body.data().await.is_none());
+        assert_eq!(body.trailers().await.unwrap(), Some(grpc_trailers()));
+    }
+
+    #[tokio::test]
+    async fn non_grpc_request_over_h2_is_routed_by_authority() {
+        let backend_addr = start_backend(false).await;
+        let proxy_addr = start_proxy(backend_addr).await;
+
+        let response = send_h2(proxy_addr, "/", false).await;
+        assert_eq!(response.status(), hyper::StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body, "hello");
+    }
+}
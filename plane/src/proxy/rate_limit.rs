@@ -0,0 +1,265 @@
+use crate::types::ClusterName;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Once the number of tracked buckets exceeds this, buckets that have refilled
+/// completely are discarded (they are indistinguishable from a fresh bucket).
+const MAX_BUCKETS_BEFORE_PRUNE: usize = 10_000;
+
+/// Minimum time between prunes, so that a map which stays above
+/// `MAX_BUCKETS_BEFORE_PRUNE` is not scanned on every request.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Number of tokens added to each bucket per second.
+    pub requests_per_second: f64,
+
+    /// Maximum number of tokens a bucket can hold, i.e. the largest burst of
+    /// requests that is allowed through at once.
+    pub burst: u32,
+
+    /// If true, each client IP gets its own bucket within a cluster. Otherwise,
+    /// all clients of a cluster share one bucket.
+    pub per_ip: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(config: &RateLimitConfig, now: Instant) -> Self {
+        Self {
+            tokens: config.burst as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, config: &RateLimitConfig, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * config.requests_per_second)
+            .min(config.burst as f64);
+        self.last_refill = now;
+    }
+
+    /// Attempts to take a token. On failure, returns the time until a token
+    /// will be available.
+    fn try_take(&mut self, config: &RateLimitConfig, now: Instant) -> Result<(), Duration> {
+        self.refill(config, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - self.tokens;
+            Err(Duration::from_secs_f64(
+                missing / config.requests_per_second,
+            ))
+        }
+    }
+
+    fn is_full(&self, config: &RateLimitConfig, now: Instant) -> bool {
+        let mut bucket = *self;
+        bucket.refill(config, now);
+        bucket.tokens >= config.burst as f64
+    }
+}
+
+type BucketKey = (ClusterName, Option<IpAddr>);
+
+struct Buckets {
+    buckets: HashMap<BucketKey, TokenBucket>,
+    last_prune: Option<Instant>,
+}
+
+impl Buckets {
+    fn prune_if_needed(&mut self, config: &RateLimitConfig, now: Instant) {
+        if self.buckets.len() <= MAX_BUCKETS_BEFORE_PRUNE {
+            return;
+        }
+        if let Some(last_prune) = self.last_prune {
+            if now.saturating_duration_since(last_prune) < PRUNE_INTERVAL {
+                return;
+            }
+        }
+
+        self.buckets
+            .retain(|_, bucket| !bucket.is_full(config, now));
+        self.last_prune = Some(now);
+    }
+}
+
+/// Token-bucket rate limiter for proxied requests, keyed by cluster and
+/// (optionally) by client IP. Shared between all of the proxy's connection tasks.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                last_prune: None,
+            }),
+        }
+    }
+
+    /// Records a request from `ip` to `cluster`. If the request exceeds the
+    /// limit, returns the time the client should wait before retrying.
+    pub fn check(&self, cluster: &ClusterName, ip: IpAddr) -> Result<(), Duration> {
+        self.check_at(cluster, ip, Instant::now())
+    }
+
+    fn check_at(&self, cluster: &ClusterName, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let key = (cluster.clone(), self.config.per_ip.then_some(ip));
+        let mut buckets = self
+            .buckets
+            .lock()
+            .expect("Rate limiter lock was poisoned.");
+
+        buckets.prune_if_needed(&self.config, now);
+
+        buckets
+            .buckets
+            .entry(key)
+            .or_insert_with(|| TokenBucket::new(&self.config, now))
+            .try_take(&self.config, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn cluster() -> ClusterName {
+        ClusterName::from_str("plane.test").unwrap()
+    }
+
+    fn limiter(per_ip: bool) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            requests_per_second: 2.0,
+            burst: 3,
+            per_ip,
+        })
+    }
+
+    const IP_1: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+    const IP_2: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+
+    #[test]
+    fn requests_beyond_burst_are_limited() {
+        let limiter = limiter(false);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at(&cluster(), IP_1, now).is_ok());
+        }
+
+        let retry_after = limiter.check_at(&cluster(), IP_1, now).unwrap_err();
+        assert_eq!(retry_after, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn bucket_refills_over_time() {
+        let limiter = limiter(false);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at(&cluster(), IP_1, now).is_ok());
+        }
+        assert!(limiter.check_at(&cluster(), IP_1, now).is_err());
+
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.check_at(&cluster(), IP_1, later).is_ok());
+        assert!(limiter.check_at(&cluster(), IP_1, later).is_err());
+
+        // Refill is capped at the burst size.
+        let much_later = later + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.check_at(&cluster(), IP_1, much_later).is_ok());
+        }
+        assert!(limiter.check_at(&cluster(), IP_1, much_later).is_err());
+    }
+
+    #[test]
+    fn clusters_have_separate_buckets() {
+        let limiter = limiter(false);
+        let other = ClusterName::from_str("other.test").unwrap();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at(&cluster(), IP_1, now).is_ok());
+        }
+        assert!(limiter.check_at(&cluster(), IP_1, now).is_err());
+        assert!(limiter.check_at(&other, IP_1, now).is_ok());
+    }
+
+    #[test]
+    fn shared_bucket_without_per_ip() {
+        let limiter = limiter(false);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at(&cluster(), IP_1, now).is_ok());
+        }
+        assert!(limiter.check_at(&cluster(), IP_2, now).is_err());
+    }
+
+    #[test]
+    fn per_ip_buckets() {
+        let limiter = limiter(true);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at(&cluster(), IP_1, now).is_ok());
+        }
+        assert!(limiter.check_at(&cluster(), IP_1, now).is_err());
+        assert!(limiter.check_at(&cluster(), IP_2, now).is_ok());
+    }
+
+    fn bucket_count(limiter: &RateLimiter) -> usize {
+        limiter.buckets.lock().unwrap().buckets.len()
+    }
+
+    #[test]
+    fn full_buckets_pruned_at_most_once_per_interval() {
+        let limiter = limiter(true);
+        let now = Instant::now();
+
+        for i in 0..=MAX_BUCKETS_BEFORE_PRUNE as u32 {
+            let ip = IpAddr::V4(std::net::Ipv4Addr::from(i));
+            assert!(limiter.check_at(&cluster(), ip, now).is_ok());
+        }
+        assert_eq!(bucket_count(&limiter), MAX_BUCKETS_BEFORE_PRUNE + 1);
+
+        // Every bucket has refilled, so the next request prunes them all.
+        let later = now + Duration::from_secs(60);
+        assert!(limiter.check_at(&cluster(), IP_1, later).is_ok());
+        assert_eq!(bucket_count(&limiter), 1);
+
+        for i in 0..=MAX_BUCKETS_BEFORE_PRUNE as u32 {
+            let ip = IpAddr::V4(std::net::Ipv4Addr::from(i));
+            assert!(limiter.check_at(&cluster(), ip, later).is_ok());
+        }
+
+        // Within the interval, the map is left alone even though it is over the limit.
+        let soon_after = later + Duration::from_secs(5);
+        assert!(limiter.check_at(&cluster(), IP_2, soon_after).is_ok());
+        assert!(bucket_count(&limiter) > MAX_BUCKETS_BEFORE_PRUNE);
+
+        let after_interval = later + PRUNE_INTERVAL;
+        assert!(limiter.check_at(&cluster(), IP_2, after_interval).is_ok());
+        assert!(bucket_count(&limiter) < MAX_BUCKETS_BEFORE_PRUNE);
+    }
+}
@@ -20,6 +20,7 @@ const PATH_PREFIX_HEADER: &str = "x-verified-path";
 const BACKEND_ID_HEADER: &str = "x-verified-backend";
 const X_FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
 const X_FORWARDED_PROTO_HEADER: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST_HEADER: &str = "x-forwarded-host";
 
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
 pub enum RequestRewriterError {
@@ -44,7 +45,16 @@ pub struct RequestRewriter {
 
 impl RequestRewriter {
     pub fn new(request: Request<Body>, remote_meta: ForwardableRequestInfo) -> Option<Self> {
-        let (parts, body) = request.into_parts();
+        let (mut parts, body) = request.into_parts();
+
+        // HTTP/2 requests carry the host in the :authority pseudo-header instead of a Host
+        // header. Copy it into a Host header so that routing and forwarding treat both
+        // protocols alike.
+        if !parts.headers.contains_key(HOST) {
+            if let Some(host) = parts.uri.authority().and_then(host_from_authority) {
+                parts.headers.insert(HOST, host);
+            }
+        }
 
         let mut uri_parts = parts.uri.clone().into_parts();
         uri_parts.scheme = Some("http".parse().expect("Scheme is valid."));
@@ -157,6 +167,15 @@ impl RequestRewriter {
     }
 }
 
+/// Returns the host and port of an authority as a Host header value, dropping any userinfo.
+fn host_from_authority(authority: &uri::Authority) -> Option<HeaderValue> {
+    let host = match authority.port() {
+        Some(port) => format!("{}:{}", authority.host(), port),
+        None => authority.host().to_string(),
+    };
+    HeaderValue::from_str(&host).ok()
+}
+
 fn clone_request_with_empty_body(
     parts: &request::Parts,
     route_info: &RouteInfo,
@@ -256,6 +275,12 @@ fn set_headers_from_route_info(
         );
     }
 
+    if headers.get(X_FORWARDED_HOST_HEADER).is_none() {
+        if let Some(host) = headers.get(HOST).cloned() {
+            headers.insert(X_FORWARDED_HOST_HEADER, host);
+        }
+    }
+
     headers.insert(
         AUTH_SECRET_HEADER,
         HeaderValue::from_str(&route_info.secret_token.to_string()).expect("Secret is valid."),
@@ -284,3 +309,92 @@ fn set_headers_from_route_info(
             .expect("Backend ID is a valid header value."),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        log_types::BackendAddr,
+        names::{BackendName, Name},
+        proxy::Protocol,
+        types::SecretToken,
+    };
+
+    fn route_info() -> RouteInfo {
+        RouteInfo {
+            backend_id: BackendName::new_random(),
+            address: BackendAddr("127.0.0.1:8080".parse().unwrap()),
+            secret_token: SecretToken::from("secret".to_string()),
+            cluster: ClusterName::from_str("plane.test").unwrap(),
+            user: None,
+            user_data: None,
+            subdomain: None,
+        }
+    }
+
+    fn forwarded_headers(headers: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            header_map.insert(*name, HeaderValue::from_static(value));
+        }
+
+        let remote_meta = ForwardableRequestInfo {
+            ip: "10.0.0.2".parse().unwrap(),
+            protocol: Protocol::Https,
+        };
+        set_headers_from_route_info(
+            &mut header_map,
+            &route_info(),
+            &Uri::from_static("/token/"),
+            remote_meta,
+        );
+        header_map
+    }
+
+    #[test]
+    fn host_is_taken_from_authority_without_host_header() {
+        let request = Request::builder()
+            .version(hyper::Version::HTTP_2)
+            .uri("https://user@foo.plane.test:443/token/path")
+            .body(Body::empty())
+            .unwrap();
+        let remote_meta = ForwardableRequestInfo {
+            ip: "10.0.0.2".parse().unwrap(),
+            protocol: Protocol::Https,
+        };
+        let rewriter = RequestRewriter::new(request, remote_meta).unwrap();
+
+        let cluster = ClusterName::from_str("plane.test").unwrap();
+        assert_eq!(rewriter.get_subdomain(&cluster), Ok(Some("foo")));
+
+        let request = rewriter.into_request(&route_info());
+        assert_eq!(request.headers()[HOST], "foo.plane.test:443");
+        assert_eq!(
+            request.headers()[X_FORWARDED_HOST_HEADER],
+            "foo.plane.test:443"
+        );
+    }
+
+    #[test]
+    fn forwarding_headers_are_set() {
+        let headers = forwarded_headers(&[("host", "plane.test")]);
+
+        assert_eq!(headers[X_FORWARDED_FOR_HEADER], "10.0.0.2");
+        assert_eq!(headers[X_FORWARDED_PROTO_HEADER], "https");
+        assert_eq!(headers[X_FORWARDED_HOST_HEADER], "plane.test");
+    }
+
+    #[test]
+    fn existing_forwarding_headers_are_kept() {
+        let headers = forwarded_headers(&[
+            ("host", "plane.test"),
+            ("x-forwarded-for", "10.0.0.1"),
+            ("x-forwarded-proto", "http"),
+            ("x-forwarded-host", "example.com"),
+        ]);
+
+        assert_eq!(headers[X_FORWARDED_FOR_HEADER], "10.0.0.1, 10.0.0.2");
+        assert_eq!(headers[X_FORWARDED_PROTO_HEADER], "http");
+        assert_eq!(headers[X_FORWARDED_HOST_HEADER], "example.com");
+    }
+}
@@ -1,26 +1,46 @@
 use super::rewriter::RequestRewriterError;
 use crate::types::ClusterName;
 
-// If a cluster name does not specify a port, :443 is implied.
+// If a host header does not specify a port, :443 is implied.
 // Most browsers will not specify it, but some (e.g. the `ws` websocket client in Node.js)
-// will, so we strip it.
-const HTTPS_PORT_SUFFIX: &str = ":443";
+// will.
+const HTTPS_PORT: u16 = 443;
 
 /// Returns Ok(Some(subdomain)) if a subdomain is found.
 /// Returns Ok(None) if no subdomain is found, but the host header matches the cluster name.
 /// Returns Err(RequestRewriterError::InvalidHostHeader) if the host header does not
 /// match the cluster name.
+///
+/// Hosts are compared without their port. If the cluster name specifies a port, the
+/// host header's port must match it; otherwise any port is accepted.
 pub fn subdomain_from_host<'a>(
     host: &'a str,
     cluster: &ClusterName,
 ) -> Result<Option<&'a str>, RequestRewriterError> {
-    let host = if let Some(host) = host.strip_suffix(HTTPS_PORT_SUFFIX) {
-        host
-    } else {
-        host
+    let (host, port) = match host.rsplit_once(':') {
+        Some((host, port)) => {
+            let Ok(port) = port.parse::<u16>() else {
+                tracing::warn!(host, port, "Host header has an invalid port.");
+                return Err(RequestRewriterError::InvalidHostHeader);
+            };
+            (host, port)
+        }
+        None => (host, HTTPS_PORT),
     };
 
-    if let Some(subdomain) = host.strip_suffix(cluster.as_str()) {
+    if let Some(cluster_port) = cluster.port() {
+        if port != cluster_port {
+            tracing::warn!(
+                host,
+                port,
+                cluster_port,
+                "Host header port does not match cluster."
+            );
+            return Err(RequestRewriterError::InvalidHostHeader);
+        }
+    }
+
+    if let Some(subdomain) = host.strip_suffix(cluster.host()) {
         if subdomain.is_empty() {
             // Subdomain exactly matches cluster name.
             Ok(None)
@@ -93,7 +113,24 @@ mod tests {
 
     #[test]
     fn port_must_match() {
-        let host = "foobar.myhost:8080";
+        let host = "foobar.myhost:8081";
+        let cluster = ClusterName::from_str("myhost:8080").unwrap();
+        assert_eq!(
+            subdomain_from_host(host, &cluster),
+            Err(RequestRewriterError::InvalidHostHeader)
+        );
+    }
+
+    #[test]
+    fn any_port_without_cluster_port() {
+        let host = "foobar.plane.test:8443";
+        let cluster = ClusterName::from_str("plane.test").unwrap();
+        assert_eq!(subdomain_from_host(host, &cluster), Ok(Some("foobar")));
+    }
+
+    #[test]
+    fn invalid_port() {
+        let host = "foobar.myhost:abc";
         let cluster = ClusterName::from_str("myhost").unwrap();
         assert_eq!(
             subdomain_from_host(host, &cluster),
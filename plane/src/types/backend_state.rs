@@ -116,6 +116,10 @@ pub enum BackendState {
         termination: Option<TerminationKind>,
         reason: Option<TerminationReason>,
         exit_code: Option<i32>,
+        /// A human-readable description of why the backend failed, if it
+        /// terminated because of an error rather than an exit or a request.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
     },
 }
 
@@ -191,6 +195,7 @@ impl valuable::Valuable for BackendState {
                 termination,
                 reason,
                 exit_code,
+                message,
             } => {
                 visit.visit_entry(
                     valuable::Value::String("status"),
@@ -206,6 +211,12 @@ impl valuable::Valuable for BackendState {
                 );
                 visit.visit_entry(valuable::Value::String("reason"), reason.as_value());
                 visit.visit_entry(valuable::Value::String("exit_code"), exit_code.as_value());
+                if let Some(message) = message {
+                    visit.visit_entry(
+                        valuable::Value::String("message"),
+                        valuable::Value::String(message),
+                    );
+                }
             }
         }
     }
@@ -223,7 +234,7 @@ impl valuable::Mappable for BackendState {
             BackendState::Ready { .. } => (1, Some(2)),
             BackendState::Terminating { .. } => (1, Some(4)),
             BackendState::HardTerminating { .. } => (1, Some(3)),
-            BackendState::Terminated { .. } => (2, Some(5)),
+            BackendState::Terminated { .. } => (2, Some(6)),
         }
     }
 }
@@ -236,6 +247,8 @@ pub enum TerminationReason {
     KeyExpired,
     Lost,
     StartupTimeout,
+    StartupFailed,
+    OutOfMemory,
     InternalError,
 }
 
@@ -247,6 +260,8 @@ impl valuable::Valuable for TerminationReason {
             TerminationReason::KeyExpired => valuable::Value::String("key_expired"),
             TerminationReason::Lost => valuable::Value::String("lost"),
             TerminationReason::StartupTimeout => valuable::Value::String("startup_timeout"),
+            TerminationReason::StartupFailed => valuable::Value::String("startup_failed"),
+            TerminationReason::OutOfMemory => valuable::Value::String("out_of_memory"),
             TerminationReason::InternalError => valuable::Value::String("internal_error"),
         }
     }
@@ -339,6 +354,59 @@ impl BackendState {
         }
     }
 
+    /// Transitions a backend that failed before it had a running process
+    /// (for example, because its image could not be pulled) directly to
+    /// terminated, recording a description of the failure.
+    pub fn to_failed(&self, message: String) -> BackendState {
+        if self.status() >= BackendStatus::Terminated {
+            tracing::warn!(%message, state=?self, "to_failed called on terminated backend");
+            return self.clone();
+        }
+
+        BackendState::Terminated {
+            last_status: self.status(),
+            termination: None,
+            reason: Some(TerminationReason::StartupFailed),
+            exit_code: None,
+            message: Some(message),
+        }
+    }
+
+    /// Transitions a backend whose process was killed for exceeding its memory
+    /// limit to terminated. This takes precedence over any pending termination,
+    /// since it is the reason the process actually exited.
+    pub fn to_out_of_memory(&self, exit_code: Option<i32>) -> BackendState {
+        if self.status() >= BackendStatus::Terminated {
+            tracing::warn!(?exit_code, state=?self, "to_out_of_memory called on terminated backend");
+            return self.clone();
+        }
+
+        BackendState::Terminated {
+            last_status: self.status(),
+            termination: None,
+            reason: Some(TerminationReason::OutOfMemory),
+            exit_code,
+            message: Some("Backend exceeded its memory limit.".to_string()),
+        }
+    }
+
+    /// Transitions a backend whose drone stopped sending heartbeats to terminated.
+    /// The controller does this on the drone's behalf, since the drone cannot.
+    pub fn to_lost(&self) -> BackendState {
+        if self.status() >= BackendStatus::Terminated {
+            tracing::warn!(state=?self, "to_lost called on terminated backend");
+            return self.clone();
+        }
+
+        BackendState::Terminated {
+            last_status: self.status(),
+            termination: None,
+            reason: Some(TerminationReason::Lost),
+            exit_code: None,
+            message: Some("Drone was lost.".to_string()),
+        }
+    }
+
     pub fn to_terminated(&self, exit_code: Option<i32>) -> BackendState {
         match self {
             BackendState::Terminated { .. } => {
@@ -353,6 +421,7 @@ impl BackendState {
                 termination: Some(TerminationKind::Hard),
                 reason: Some(*reason),
                 exit_code,
+                message: None,
             },
             #[allow(deprecated)]
             BackendState::Terminating {
@@ -364,12 +433,14 @@ impl BackendState {
                 termination: Some(*termination),
                 reason: Some(*reason),
                 exit_code,
+                message: None,
             },
             _ => BackendState::Terminated {
                 last_status: self.status(),
                 termination: None,
                 reason: None,
                 exit_code,
+                message: None,
             },
         }
     }
@@ -460,3 +531,192 @@ impl From<BackendRow> for BackendStatusStreamEntry {
         Self::from_state(row.state, row.last_status_time)
     }
 }
+
+/// The chronological list of status transitions of a single backend.
+#[derive(Clone, Debug, Default)]
+pub struct BackendStatusHistory {
+    transitions: Vec<(DateTime<Utc>, BackendStatus)>,
+}
+
+impl BackendStatusHistory {
+    /// Constructs a history from `(time, status)` pairs. The pairs are sorted
+    /// by time, so they do not need to be provided in order.
+    pub fn new(mut transitions: Vec<(DateTime<Utc>, BackendStatus)>) -> Self {
+        transitions.sort_by_key(|(time, _)| *time);
+        Self { transitions }
+    }
+
+    pub fn transitions(&self) -> &[(DateTime<Utc>, BackendStatus)] {
+        &self.transitions
+    }
+
+    /// The time the backend spent in the given status, from when it entered the
+    /// status until the next transition. If the status is the backend's current
+    /// status, the span is measured until `now`.
+    ///
+    /// Returns `None` if the backend never entered the status.
+    pub fn duration_in(
+        &self,
+        status: BackendStatus,
+        now: DateTime<Utc>,
+    ) -> Option<chrono::Duration> {
+        let index = self.transitions.iter().position(|(_, s)| *s == status)?;
+        let (entered, _) = self.transitions[index];
+        let exited = self
+            .transitions
+            .get(index + 1)
+            .map(|(time, _)| *time)
+            .unwrap_or(now);
+
+        Some(exited - entered)
+    }
+
+    /// The time between the backend entering the `Starting` status and
+    /// entering the `Ready` status.
+    ///
+    /// Returns `None` if the backend did not pass through both statuses.
+    pub fn time_to_ready(&self) -> Option<chrono::Duration> {
+        let entered = |status: BackendStatus| {
+            self.transitions
+                .iter()
+                .find(|(_, s)| *s == status)
+                .map(|(time, _)| *time)
+        };
+
+        Some(entered(BackendStatus::Ready)? - entered(BackendStatus::Starting)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn history(start: DateTime<Utc>, statuses: &[(i64, BackendStatus)]) -> BackendStatusHistory {
+        BackendStatusHistory::new(
+            statuses
+                .iter()
+                .map(|(secs, status)| (start + Duration::try_seconds(*secs).unwrap(), *status))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn duration_in_past_and_current_status() {
+        let start = Utc::now();
+        let history = history(
+            start,
+            &[
+                (0, BackendStatus::Scheduled),
+                (1, BackendStatus::Loading),
+                (5, BackendStatus::Starting),
+                (7, BackendStatus::Waiting),
+                (10, BackendStatus::Ready),
+            ],
+        );
+        let now = start + Duration::try_seconds(30).unwrap();
+
+        assert_eq!(
+            history.duration_in(BackendStatus::Loading, now),
+            Duration::try_seconds(4)
+        );
+        assert_eq!(
+            history.duration_in(BackendStatus::Ready, now),
+            Duration::try_seconds(20)
+        );
+        assert_eq!(history.time_to_ready(), Duration::try_seconds(5));
+    }
+
+    #[test]
+    fn never_entered_status_is_none() {
+        let start = Utc::now();
+        let history = history(
+            start,
+            &[
+                (0, BackendStatus::Scheduled),
+                (1, BackendStatus::Loading),
+                (2, BackendStatus::Terminated),
+            ],
+        );
+
+        assert_eq!(history.duration_in(BackendStatus::Ready, start), None);
+        assert_eq!(history.time_to_ready(), None);
+        assert_eq!(
+            history.duration_in(
+                BackendStatus::Terminated,
+                start + Duration::try_seconds(2).unwrap()
+            ),
+            Some(Duration::zero())
+        );
+    }
+
+    #[test]
+    fn failed_state_round_trips_message() {
+        let state = BackendState::Loading.to_failed("image not found".to_string());
+        assert_eq!(state.status(), BackendStatus::Terminated);
+
+        let json = serde_json::to_value(&state).unwrap();
+        assert_eq!(json["reason"], "startupfailed");
+        assert_eq!(json["message"], "image not found");
+        assert_eq!(serde_json::from_value::<BackendState>(json).unwrap(), state);
+    }
+
+    #[test]
+    fn out_of_memory_state() {
+        let ready = BackendState::Ready {
+            address: BackendAddr("127.0.0.1:8080".parse().unwrap()),
+        };
+        let state = ready.to_out_of_memory(Some(137));
+        let BackendState::Terminated {
+            last_status,
+            reason,
+            exit_code,
+            ..
+        } = &state
+        else {
+            panic!("Expected terminated state, got {:?}", state);
+        };
+        assert_eq!(*last_status, BackendStatus::Ready);
+        assert_eq!(*reason, Some(TerminationReason::OutOfMemory));
+        assert_eq!(*exit_code, Some(137));
+
+        let json = serde_json::to_value(&state).unwrap();
+        assert_eq!(json["reason"], "outofmemory");
+
+        // An already-terminated backend is not changed.
+        assert_eq!(state.to_out_of_memory(None), state);
+    }
+
+    #[test]
+    fn lost_state() {
+        let state = BackendState::Starting.to_lost();
+        let BackendState::Terminated {
+            last_status,
+            reason,
+            ..
+        } = &state
+        else {
+            panic!("Expected terminated state, got {:?}", state);
+        };
+        assert_eq!(*last_status, BackendStatus::Starting);
+        assert_eq!(*reason, Some(TerminationReason::Lost));
+        assert_eq!(state.to_lost(), state);
+    }
+
+    #[test]
+    fn terminated_state_without_message_deserializes() {
+        let json = serde_json::json!({
+            "status": "terminated",
+            "last_status": "ready",
+            "termination": "soft",
+            "reason": "swept",
+            "exit_code": 0,
+        });
+
+        let state: BackendState = serde_json::from_value(json).unwrap();
+        assert!(matches!(
+            state,
+            BackendState::Terminated { message: None, .. }
+        ));
+    }
+}
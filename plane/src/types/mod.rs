@@ -1,5 +1,6 @@
 use crate::{
     client::PlaneClient,
+    controller::error::ApiError,
     names::{AnyNodeName, BackendName, ControllerName, DroneName},
     util::{random_prefixed_string, random_token},
 };
@@ -33,13 +34,29 @@ impl Display for NodeId {
     }
 }
 
+/// The name of a cluster, which is the hostname (and optional port) that its
+/// proxies serve. Parsing normalizes the hostname: it is lowercased and a single
+/// trailing dot is removed, so that names compare equal however they were written.
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, valuable::Valuable)]
+#[serde(try_from = "String")]
 pub struct ClusterName(String);
 
 impl ClusterName {
     pub fn is_https(&self) -> bool {
-        let port = self.0.split_once(':').map(|x| x.1);
-        port.is_none() || port == Some("443")
+        matches!(self.port(), None | Some(443))
+    }
+
+    /// The hostname (or IP) portion of the cluster name, without the port.
+    pub fn host(&self) -> &str {
+        self.0.split_once(':').map_or(&self.0, |(host, _)| host)
+    }
+
+    /// The port portion of the cluster name, if one was given.
+    pub fn port(&self) -> Option<u16> {
+        // The port is validated when the name is parsed.
+        self.0
+            .split_once(':')
+            .and_then(|(_, port)| port.parse().ok())
     }
 
     pub fn as_str(&self) -> &str {
@@ -61,12 +78,29 @@ impl FromStr for ClusterName {
         let host = parts.next().ok_or("missing hostname or ip")?;
         let port = parts.next();
 
-        url::Host::parse(host).map_err(|_| "invalid hostname or ip")?;
+        let host = host.strip_suffix('.').unwrap_or(host);
+        if host.is_empty() {
+            return Err("missing hostname or ip");
+        }
+
+        // Host::parse lowercases domain names, so its output is the normalized form.
+        let host = url::Host::parse(host).map_err(|_| "invalid hostname or ip")?;
+        let mut name = host.to_string();
+
         if let Some(port) = port {
-            port.parse::<u16>().map_err(|_| "invalid port")?;
+            let port = port.parse::<u16>().map_err(|_| "invalid port")?;
+            name = format!("{}:{}", name, port);
         }
 
-        Ok(Self(s.to_string()))
+        Ok(Self(name))
+    }
+}
+
+impl TryFrom<String> for ClusterName {
+    type Error = &'static str;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::from_str(&s)
     }
 }
 
@@ -227,6 +261,9 @@ pub struct DockerExecutorConfig {
     pub credentials: Option<DockerRegistryAuth>,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Arguments to run the container with, replacing the image's default command.
+    #[serde(default)]
+    pub args: Option<Vec<String>>,
     #[serde(default)]
     pub resource_limits: ResourceLimits,
     pub mount: Option<Mount>,
@@ -239,6 +276,7 @@ impl DockerExecutorConfig {
             image: image.into(),
             pull_policy: None,
             env: HashMap::default(),
+            args: None,
             resource_limits: ResourceLimits::default(),
             credentials: None,
             mount: None,
@@ -279,6 +317,29 @@ pub struct SpawnConfig {
     pub use_static_token: bool,
 
     pub subdomain: Option<Subdomain>,
+
+    /// If provided, the backend is placed on this drone instead of one chosen by the
+    /// scheduler. The drone must belong to the cluster and pool, be ready, not be
+    /// draining, and have a recent heartbeat.
+    #[serde(default)]
+    pub drone: Option<DroneName>,
+
+    /// If provided, and no drone is available, the connect request waits up to this
+    /// many seconds (capped at MAX_SPAWN_WAIT_SECONDS) for one to become available
+    /// instead of failing immediately.
+    #[serde(default)]
+    pub max_wait_seconds: Option<i32>,
+
+    /// Clusters to try, in order, if no drone is available in the primary cluster.
+    /// The cluster field of the connect response names the cluster the backend was placed in.
+    /// Ignored when a drone is pinned.
+    #[serde(default)]
+    pub fallback_clusters: Vec<ClusterName>,
+
+    /// If true, and the backend's drone is lost before the backend becomes ready, the
+    /// controller will spawn a replacement backend for the same key on another drone.
+    #[serde(default)]
+    pub reschedule: bool,
 }
 
 #[derive(
@@ -394,6 +455,10 @@ pub struct ConnectResponse {
 
     /// The drone that spawned this backend, if the request resulted in a spawn.
     pub drone: Option<DroneName>,
+
+    /// The cluster the backend runs in. Absent in responses from older controllers.
+    #[serde(default)]
+    pub cluster: Option<ClusterName>,
 }
 
 impl ConnectResponse {
@@ -428,6 +493,7 @@ impl ConnectResponse {
             secret_token,
             status_url,
             drone,
+            cluster: Some(cluster.clone()),
         }
     }
 }
@@ -457,6 +523,75 @@ impl TryFrom<String> for NodeKind {
     }
 }
 
+/// Upper bound on BatchConnectRequest::count.
+pub const MAX_BATCH_CONNECT_COUNT: u32 = 100;
+
+/// A request to spawn several identical backends, e.g. to pre-warm a pool.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BatchConnectRequest {
+    /// The connect request to run for each backend. It must include a spawn config,
+    /// and must not include a key or backend ID, since each backend gets its own.
+    /// The spawn config must not set max_wait_seconds, since the spawns run one after
+    /// another and each would wait separately.
+    pub request: ConnectRequest,
+
+    /// The number of backends to spawn, at most MAX_BATCH_CONNECT_COUNT.
+    pub count: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchConnectResponse {
+    /// One result per requested backend. Failed spawns (e.g. because no drone had
+    /// capacity) are reported individually and do not affect the others.
+    pub results: Vec<Result<ConnectResponse, ApiError>>,
+}
+
+/// Why the scheduler would not place a backend on a drone.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DroneExclusionReason {
+    /// The drone is not in the requested pool.
+    WrongPool,
+    Draining,
+    /// The drone is not ready, or it or its controller has a stale heartbeat.
+    Unhealthy,
+    /// The drone has reached the backend limit it advertised.
+    AtCapacity,
+}
+
+/// A drone considered by a scheduling dry run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleCandidate {
+    pub drone: DroneName,
+
+    /// Number of non-terminated backends on the drone. Among eligible drones, the
+    /// scheduler prefers the one with the fewest, breaking ties at random.
+    pub backend_count: u32,
+
+    /// Why the drone is ineligible, or None if it is eligible.
+    pub excluded: Option<DroneExclusionReason>,
+}
+
+/// Candidate drones for one of the clusters a spawn may be placed in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClusterScheduleCandidates {
+    pub cluster: ClusterName,
+
+    /// Eligible drones in order of preference, followed by excluded drones.
+    pub candidates: Vec<ScheduleCandidate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleDryRunResponse {
+    /// The cluster and drone the spawn would be placed on, or None if no drone is
+    /// available.
+    pub placement: Option<(ClusterName, DroneName)>,
+
+    /// The candidates of each cluster considered, in the order they were considered.
+    /// Fallback clusters are only considered if no earlier cluster has an eligible drone.
+    pub clusters: Vec<ClusterScheduleCandidates>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct RevokeRequest {
     pub backend_id: BackendName,
@@ -475,6 +610,9 @@ pub struct DroneState {
     #[serde(with = "crate::serialization::serialize_duration_as_seconds")]
     pub last_heartbeat_age: Duration,
     pub backend_count: u32,
+    /// The maximum number of non-terminated backends the drone advertised, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_backends: Option<u32>,
     pub node: NodeState,
 }
 
@@ -538,3 +676,63 @@ impl Deref for Subdomain {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cluster_name_is_normalized() {
+        let cases = [
+            ("plane.test", "plane.test"),
+            ("Plane.TEST", "plane.test"),
+            ("plane.test.", "plane.test"),
+            ("plane.test:8443", "plane.test:8443"),
+            ("PLANE.test.:8443", "plane.test:8443"),
+            ("127.0.0.1:9090", "127.0.0.1:9090"),
+        ];
+
+        for (input, expected) in cases {
+            let cluster = ClusterName::from_str(input).unwrap();
+            assert_eq!(cluster.as_str(), expected, "normalizing {}", input);
+        }
+    }
+
+    #[test]
+    fn cluster_name_host_and_port() {
+        let cluster = ClusterName::from_str("plane.test:8443").unwrap();
+        assert_eq!(cluster.host(), "plane.test");
+        assert_eq!(cluster.port(), Some(8443));
+        assert!(!cluster.is_https());
+        assert_eq!(cluster.to_string().parse::<ClusterName>().unwrap(), cluster);
+
+        let cluster = ClusterName::from_str("plane.test").unwrap();
+        assert_eq!(cluster.host(), "plane.test");
+        assert_eq!(cluster.port(), None);
+        assert!(cluster.is_https());
+
+        let cluster = ClusterName::from_str("plane.test:443").unwrap();
+        assert_eq!(cluster.host(), "plane.test");
+        assert_eq!(cluster.port(), Some(443));
+        assert!(cluster.is_https());
+    }
+
+    #[test]
+    fn invalid_cluster_names_are_rejected() {
+        for input in ["", ".", "plane test", "plane.test:port", "plane.test:99999"] {
+            assert!(
+                ClusterName::from_str(input).is_err(),
+                "{} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn cluster_name_deserialization_normalizes() {
+        let cluster: ClusterName = serde_json::from_str("\"Plane.Test.\"").unwrap();
+        assert_eq!(cluster.as_str(), "plane.test");
+
+        assert!(serde_json::from_str::<ClusterName>("\"plane test\"").is_err());
+    }
+}